@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-fn mul(x: &Vec<f64>, y: &Vec<f64>) -> f64 {
+fn mul(x: &[f64], y: &[f64]) -> f64 {
     x.iter().zip(y.iter()).map(|(&a, &b)| a * b).sum()
 }
 
@@ -30,14 +30,14 @@ pub fn test()-> Vec<i16>{
         let _i= play_move(1<<(i&1), i & 127 == 0);
         x.push(_i);
     }
-    return x;
+    x
 }
 
 
 
 
 fn bench_prime_nos(c: &mut Criterion) {
-    c.bench_function("Prime No Benchmark", |b| b.iter(|| test() ));
+    c.bench_function("Prime No Benchmark", |b| b.iter(test));
 }
 
 // Create a benchmark group
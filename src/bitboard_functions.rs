@@ -36,6 +36,7 @@ pub fn get_lsb(i: i32)-> i32{
 /// Returns:
 /// 
 /// The function `get_without_lsb` returns the input `i` with the least significant bit (LSB) set to 0.
+#[allow(clippy::doc_lazy_continuation)]
 pub fn get_without_lsb(i: i32)-> i32{
     i&(i-1)
 }
@@ -52,6 +53,7 @@ pub fn get_without_lsb(i: i32)-> i32{
 /// Returns:
 /// 
 /// A vector of individual bits of the input number `i` is being returned.
+#[allow(clippy::doc_lazy_continuation)]
 pub fn get_indi_bits(mut i: i16)-> Vec<i16>{
     let mut returner: Vec<i16>= Vec::new();
     while i!=0 {
@@ -59,4 +61,120 @@ pub fn get_indi_bits(mut i: i16)-> Vec<i16>{
         i= get_without_lsb(i as i32) as i16;
     }
     returner
-}
\ No newline at end of file
+}
+
+/// The function `to_cell` converts a single-bit move into the number of the cell it occupies,
+/// counting the cells row by row from 1 to 9.
+/// 
+/// Arguments:
+/// 
+/// * `_move`: The `_move` parameter is a bitboard with exactly one bit set.
+/// 
+/// Returns:
+/// 
+/// The cell number of the move, between 1 and 9.
+pub fn to_cell(_move: i16)-> u32{
+    _move.trailing_zeros() + 1
+}
+
+/// The cell each cell is sent to by the eight symmetries of the board: the identity, the three
+/// rotations by a quarter turn and the four reflections.
+pub const SYMMETRIES: [[u8; 9]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0],
+];
+
+/// The function `transform` applies one of the `SYMMETRIES` to a bitboard.
+/// 
+/// Arguments:
+/// 
+/// * `i`: The `i` parameter is the bitboard to be transformed.
+/// * `symmetry`: The `symmetry` parameter is the index of the symmetry in `SYMMETRIES`.
+/// 
+/// Returns:
+/// 
+/// The bitboard with every set cell moved to its image under the symmetry.
+pub fn transform(i: i16, symmetry: usize)-> i16{
+    get_indi_bits(i)
+        .into_iter()
+        .fold(0, |acc, bit| acc | 1 << SYMMETRIES[symmetry][bit.trailing_zeros() as usize])
+}
+
+/// The function `canonical` returns the representative of a position among its symmetric images,
+/// so that positions equal up to rotation and reflection can be recognised.
+/// 
+/// Arguments:
+/// 
+/// * `me`: The `me` parameter is the bitboard of one side.
+/// * `opp`: The `opp` parameter is the bitboard of the other side.
+/// 
+/// Returns:
+/// 
+/// The image of `(me, opp)` under the symmetry giving the smallest pair.
+pub fn canonical(me: i16, opp: i16)-> (i16, i16){
+    (0..SYMMETRIES.len())
+        .map(|symmetry| (transform(me, symmetry), transform(opp, symmetry)))
+        .min()
+        .unwrap_or((me, opp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    /// Every way of filling the cells, legal in a game or not.
+    fn positions() -> impl Iterator<Item = (i16, i16)> {
+        (0..3usize.pow(9)).map(|mut index| {
+            let (mut me, mut opp) = (0, 0);
+            for cell in 0..9 {
+                match index % 3 {
+                    1 => me |= 1 << cell,
+                    2 => opp |= 1 << cell,
+                    _ => {}
+                }
+                index /= 3;
+            }
+            (me, opp)
+        })
+    }
+
+    #[test]
+    fn every_symmetry_sends_lines_to_lines() {
+        for symmetry in 0..SYMMETRIES.len() {
+            let mut images: Vec<i16> = Board::WIN_PATTERNS.iter().map(|line| transform(*line, symmetry)).collect();
+            let mut lines = Board::WIN_PATTERNS.to_vec();
+            images.sort();
+            lines.sort();
+            assert_eq!(images, lines, "symmetry {}", symmetry);
+        }
+    }
+
+    #[test]
+    fn transform_keeps_the_stones() {
+        for (symmetry, images) in SYMMETRIES.iter().enumerate() {
+            for (cell, image) in images.iter().enumerate() {
+                assert_eq!(transform(1 << cell, symmetry), 1 << image);
+            }
+            assert_eq!(transform(Board::BOARD, symmetry), Board::BOARD);
+            assert_eq!(transform(0, symmetry), 0);
+        }
+    }
+
+    #[test]
+    fn canonical_is_the_same_for_every_image() {
+        for (me, opp) in positions() {
+            let position = canonical(me, opp);
+            assert!((0..SYMMETRIES.len()).any(|symmetry| (transform(me, symmetry), transform(opp, symmetry)) == position));
+            for symmetry in 0..SYMMETRIES.len() {
+                assert_eq!(canonical(transform(me, symmetry), transform(opp, symmetry)), position);
+            }
+        }
+    }
+}
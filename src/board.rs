@@ -1,3 +1,5 @@
+use crate::bitboard_functions;
+
 
 /// The code defines a struct `Board` in Rust with methods for loading board state, generating
 /// move tables, playing moves, and checking for game end conditions.
@@ -22,6 +24,7 @@
 /// * `game_states`: The `game_states` property in the `Board` struct is a vector that stores game
 /// states as `i16` values. The `gen_move_tables` method populates this vector with game states
 /// generated by calling the `gen_move_combined` method for each value in the range `1..511
+#[allow(clippy::doc_lazy_continuation)]
 #[derive(Clone)]
 pub(crate) struct Board{
     turn: bool,
@@ -31,8 +34,8 @@ pub(crate) struct Board{
 }
 
 impl Board{
-    const WIN_PATTERNS: [i16; 8]= [0b100100100, 0b10010010, 0b1001001, 0b111, 0b111000, 0b111000000, 0b100010001, 0b1010100];
-    const BOARD: i16= 0b111111111;
+    pub const WIN_PATTERNS: [i16; 8]= [0b100100100, 0b10010010, 0b1001001, 0b111, 0b111000, 0b111000000, 0b100010001, 0b1010100];
+    pub const BOARD: i16= 0b111111111;
     /// The function `load_board` in Rust updates the game board, opponent's board, turn status, and
     /// player type.
     /// 
@@ -50,6 +53,7 @@ impl Board{
     /// * `player_type`: The `player_type` parameter in the `load_board` function is a boolean value
     /// that indicates the type of player. It could be used to differentiate between different types of
     /// players, such as human players and AI players.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn new()->Self{
         Board { 
             turn: true, 
@@ -83,6 +87,7 @@ impl Board{
     /// * `turn`: The `turn` parameter in the `play_move` function is a boolean value that indicates
     /// whose turn it is to play the move. If `turn` is `true`, it means it's the player's turn, and the
     /// move will be applied to `self.my_board`. Otherwise, the move will be applied to `self.opp_board`.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn play_move(&mut self, _move: i16){
         if self.turn {
             self.my_board |= _move;
@@ -105,11 +110,13 @@ impl Board{
     /// 
     /// * `_move`: The `_move` parameter in the `undo_move` function is of type `i16`, which is a 16-bit
     /// signed integer. This parameter represents the move that needs to be undone in the game logic.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn undo_move(&mut self, _move: i16){
+        self.turn = !self.turn;
         if self.turn {
-            self.opp_board |= _move;
+            self.my_board &= !_move;
         } else {
-            self.my_board |= _move;
+            self.opp_board &= !_move;
         }
     }
 
@@ -125,12 +132,7 @@ impl Board{
         if self.my_board | self.opp_board >= 511 {
             return true;
         }
-        for i in Self::WIN_PATTERNS {
-            if i & self.my_board == i || i & self.opp_board == i {
-                return true;
-            }
-        }
-        return false;
+        self.is_a_win()
     }
 
     /// The function checks if the current game state represents a win based on predefined win patterns.
@@ -143,12 +145,29 @@ impl Board{
     /// returns `true`, indicating a win. Otherwise, it returns `false`, indicating that there is no win
     /// yet.
     pub fn is_a_win(&self) -> bool {
-        for i in Self::WIN_PATTERNS {
-            if i & self.my_board == i || i & self.opp_board == i {
-                return true;
-            }
-        }
-        return false;
+        Self::has_line(self.my_board) || Self::has_line(self.opp_board)
+    }
+
+    /// The function `has_line` checks whether the stones of one side complete a winning pattern.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `stones`: The `stones` parameter is the bitboard of the side.
+    pub fn has_line(stones: i16) -> bool {
+        Self::completed_lines(stones) != 0
+    }
+
+    /// The function `completed_lines` returns the cells of the winning patterns `stones` complete, or 0
+    /// if there is none.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `stones`: The `stones` parameter is the bitboard of the side.
+    pub fn completed_lines(stones: i16) -> i16 {
+        Self::WIN_PATTERNS
+            .iter()
+            .filter(|pattern| stones & **pattern == **pattern)
+            .fold(0, |cells, pattern| cells | pattern)
     }
 
     /// This Rust function converts a game board represented as bitboards into an array of
@@ -173,4 +192,164 @@ impl Board{
         }
         board
     }
-}
\ No newline at end of file
+    /// The function `from_notation` builds a board from a nine character string listing the cells
+    /// row by row, using `X` and `O` for the stones and `.`, `-` or `_` for empty cells. `X` is
+    /// assumed to have moved first, so the side to move follows from the number of stones.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `notation`: The `notation` parameter is the position to parse, e.g. `"X.O.X...."`.
+    /// 
+    /// Returns:
+    /// 
+    /// The parsed `Board`, or `None` if the string is malformed or the stone counts are impossible.
+    pub fn from_notation(notation: &str) -> Option<Self> {
+        let cells: Vec<char> = notation.trim().chars().collect();
+        if cells.len() != 9 {
+            return None;
+        }
+        let mut board = Board::new();
+        for (i, cell) in cells.iter().enumerate() {
+            match cell.to_ascii_uppercase() {
+                'X' => board.my_board |= 1 << i,
+                'O' => board.opp_board |= 1 << i,
+                '.' | '-' | '_' => {}
+                _ => return None,
+            }
+        }
+        let x_count = board.my_board.count_ones();
+        let o_count = board.opp_board.count_ones();
+        if x_count != o_count && x_count != o_count + 1 {
+            return None;
+        }
+        board.turn = x_count == o_count;
+        Some(board)
+    }
+
+    /// The function `to_notation` writes the board in the format read by `from_notation`.
+    /// 
+    /// Returns:
+    /// 
+    /// A nine character `String` with `X`, `O` and `.` for the cells, row by row.
+    pub fn to_notation(&self) -> String {
+        let (x_board, o_board) = if self.player_type {
+            (self.my_board, self.opp_board)
+        } else {
+            (self.opp_board, self.my_board)
+        };
+        (0..9)
+            .map(|i| {
+                if x_board & (1 << i) != 0 {
+                    'X'
+                } else if o_board & (1 << i) != 0 {
+                    'O'
+                } else {
+                    '.'
+                }
+            })
+            .collect()
+    }
+
+    /// The function `from_sides` builds the board a player sees when it is its turn to move, like the
+    /// boards the trainer hands to each side of a game.
+    ///
+    /// Arguments:
+    ///
+    /// * `to_move`: The `to_move` parameter is the bitboard of the player to move.
+    /// * `other`: The `other` parameter is the bitboard of the player who moved last.
+    pub fn from_sides(to_move: i16, other: i16) -> Self {
+        Board {
+            turn: true,
+            player_type: to_move.count_ones() == other.count_ones(),
+            my_board: to_move,
+            opp_board: other,
+        }
+    }
+
+    /// The function `get_sides` returns the stones of both players seen from the side to move.
+    /// 
+    /// Returns:
+    /// 
+    /// A tuple whose first element is the bitboard of the player to move and whose second element is
+    /// the bitboard of the player who moved last.
+    pub fn get_sides(&self) -> (i16, i16) {
+        if self.turn {
+            (self.my_board, self.opp_board)
+        } else {
+            (self.opp_board, self.my_board)
+        }
+    }
+
+    /// The function `winning_squares` returns the empty cells where each side would complete a line.
+    /// 
+    /// Returns:
+    /// 
+    /// A tuple whose first element is the bitboard of the cells winning for the player to move and whose
+    /// second element is the bitboard of the cells winning for the player who moved last, which the
+    /// player to move has to block.
+    pub fn winning_squares(&self) -> (i16, i16) {
+        let (me, opp)= self.get_sides();
+        (Self::line_completions(me, opp), Self::line_completions(opp, me))
+    }
+
+    /// The function `forks` returns the empty cells where each side would create a double threat: two
+    /// winning squares at once, of which the other side can only block one.
+    /// 
+    /// Returns:
+    /// 
+    /// A tuple whose first element is the bitboard of the forks of the player to move and whose second
+    /// element is the bitboard of the forks of the player who moved last.
+    pub fn forks(&self) -> (i16, i16) {
+        let (me, opp)= self.get_sides();
+        (Self::fork_squares(me, opp), Self::fork_squares(opp, me))
+    }
+
+    /// The function `open_lines` counts the lines each side can still complete, those without a stone
+    /// of the other side.
+    /// 
+    /// Returns:
+    /// 
+    /// A tuple whose first element is the number of open lines of the player to move and whose second
+    /// element is the number of open lines of the player who moved last.
+    pub fn open_lines(&self) -> (u32, u32) {
+        let (me, opp)= self.get_sides();
+        (Self::count_open_lines(opp), Self::count_open_lines(me))
+    }
+
+    /// The function `line_completions` returns the empty cells that complete a line of `stones`.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `stones`: The `stones` parameter is the bitboard of the side completing the line.
+    /// * `other`: The `other` parameter is the bitboard of the other side.
+    pub fn line_completions(stones: i16, other: i16) -> i16 {
+        Self::WIN_PATTERNS
+            .iter()
+            .filter(|pattern| *pattern & other == 0 && (*pattern & stones).count_ones() == 2)
+            .fold(0, |cells, pattern| cells | (pattern & !stones))
+    }
+
+    /// The function `fork_squares` returns the empty cells where `stones` would get two or more cells
+    /// completing a line.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `stones`: The `stones` parameter is the bitboard of the side forking.
+    /// * `other`: The `other` parameter is the bitboard of the other side.
+    pub fn fork_squares(stones: i16, other: i16) -> i16 {
+        bitboard_functions::get_indi_bits(!(stones | other) & Self::BOARD)
+            .into_iter()
+            .filter(|_move| Self::line_completions(stones | _move, other).count_ones() >= 2)
+            .fold(0, |cells, _move| cells | _move)
+    }
+
+    /// The function `count_open_lines` counts the winning patterns that `blocker` has no stone on,
+    /// which are the lines still open to the other side.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `blocker`: The `blocker` parameter is the bitboard of the side whose stones block lines.
+    pub fn count_open_lines(blocker: i16) -> u32 {
+        Self::WIN_PATTERNS.iter().filter(|pattern| *pattern & blocker == 0).count() as u32
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::{BTreeMap, HashSet}, fmt::Write as _, fs, path::Path, sync::Arc};
+
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    bitboard_functions::{self, SYMMETRIES},
+    board::Board,
+    error::{Error, Result},
+    player::{self, Clock, Exploring, MatchRecord, Player},
+    solver::Solver,
+};
+
+/// The struct `OpeningBook` maps positions to the moves recommended in them, each with a weight. A
+/// position and its rotations and reflections share one entry, stored under their canonical form.
+///
+/// Properties:
+///
+/// * `entries`: The `entries` property maps each canonical position, as the stones of the side to
+/// move and of the other side, to its moves and their weights, seen in the canonical position.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OpeningBook {
+    entries: BTreeMap<(i16, i16), BTreeMap<i16, u32>>,
+}
+
+impl OpeningBook {
+    /// The function `from_solver` builds a book of every best move of the perfect solver in the
+    /// positions of the first `plies` plies, all with a weight of 1.
+    pub fn from_solver(plies: u8) -> Self {
+        let mut book = OpeningBook::default();
+        let mut solver = Solver::new();
+        let mut positions = vec![Board::new()];
+        let mut seen = HashSet::new();
+        for _ in 0..plies {
+            let mut next = Vec::new();
+            for mut board in positions {
+                let (me, opp) = board.get_sides();
+                if board.has_game_ended() || !seen.insert(bitboard_functions::canonical(me, opp)) {
+                    continue;
+                }
+                for _move in solver.best_moves(&board) {
+                    book.add(&board, _move, 1);
+                }
+                for _move in bitboard_functions::get_indi_bits(board.clone().gen_move()) {
+                    let mut child = board.clone();
+                    child.play_move(_move);
+                    next.push(child);
+                }
+            }
+            positions = next;
+        }
+        book
+    }
+
+    /// The function `from_games` builds a book from played games, weighting each of the moves of
+    /// their first `plies` plies by the result of the side that played it: 2 for a win, 1 for a draw
+    /// and 0 for a loss. Moves that never avoided a loss are left out.
+    pub fn from_games<'a>(games: impl IntoIterator<Item = &'a MatchRecord>, plies: u8) -> Self {
+        let mut book = OpeningBook::default();
+        for game in games {
+            let mut board = Board::new();
+            for (ply, _move) in game.moves.iter().take(plies as usize).enumerate() {
+                let result = if ply % 2 == 0 { game.result } else { -game.result };
+                book.add(&board, *_move, (result + 1) as u32);
+                board.play_move(*_move);
+            }
+        }
+        book.entries.values_mut().for_each(|moves| moves.retain(|_, weight| *weight > 0));
+        book.entries.retain(|_, moves| !moves.is_empty());
+        book
+    }
+
+    /// The function `add` adds weight to a move of a position.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position, seen from the side to move.
+    /// * `_move`: The `_move` parameter is the move played in it.
+    /// * `weight`: The `weight` parameter is the weight added to the move.
+    pub fn add(&mut self, board: &Board, _move: i16, weight: u32) {
+        let (me, opp) = board.get_sides();
+        let (symmetry, position) = Self::canonical(me, opp);
+        let _move = bitboard_functions::transform(_move, symmetry);
+        *self.entries.entry(position).or_default().entry(_move).or_default() += weight;
+    }
+
+    /// The function `moves` returns the moves the book recommends in a position, with their weights.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position, seen from the side to move.
+    ///
+    /// Returns:
+    ///
+    /// The moves, as played in `board`, or an empty vector if the position is not in the book.
+    pub fn moves(&self, board: &Board) -> Vec<(i16, u32)> {
+        let (me, opp) = board.get_sides();
+        let (symmetry, position) = Self::canonical(me, opp);
+        let Some(moves) = self.entries.get(&position) else {
+            return Vec::new();
+        };
+        moves
+            .iter()
+            .filter_map(|(_move, weight)| {
+                let cell = SYMMETRIES[symmetry].iter().position(|image| *image as u32 == _move.trailing_zeros())?;
+                Some((1 << cell, *weight))
+            })
+            .collect()
+    }
+
+    /// The function `choose` draws one of the moves of a position in proportion to its weight.
+    ///
+    /// Returns:
+    ///
+    /// The move, or `None` if the position is not in the book.
+    pub fn choose(&self, board: &Board, rng: &mut ChaCha8Rng) -> Option<i16> {
+        self.moves(board).choose_weighted(rng, |(_, weight)| *weight).ok().map(|(_move, _)| *_move)
+    }
+
+    /// The function `len` returns the number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The function `load` reads a book written by `save`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be read.
+    ///
+    /// Returns:
+    ///
+    /// The book, or an `Error::Book` naming the first line that could not be parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut book = OpeningBook::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: &str| Error::Book { path: path.to_path_buf(), message: format!("line {}: {}", number + 1, message) };
+            let mut fields = line.split_whitespace();
+            let position = fields.next().unwrap_or_default();
+            let board = Board::from_notation(position).ok_or_else(|| invalid(&format!("'{}' is not a position", position)))?;
+            for field in fields {
+                let parsed = field.split_once(':').and_then(|(cell, weight)| Some((cell.parse::<u32>().ok()?, weight.parse::<u32>().ok()?)));
+                match parsed {
+                    Some((cell @ 1..=9, weight)) if board.clone().gen_move() & 1 << (cell - 1) != 0 => book.add(&board, 1 << (cell - 1), weight),
+                    _ => return Err(invalid(&format!("'{}' is not a free cell and a weight, e.g. 5:3", field))),
+                }
+            }
+        }
+        Ok(book)
+    }
+
+    /// The function `save` writes the book as text: one canonical position per line, followed by its
+    /// moves as cell numbers from 1 to 9 with their weights, e.g. `X...O.... 3:2 9:1`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = String::from("# Acordion opening book: a position, then the cells to play in it (1-9, row by row) with their weights\n");
+        for ((me, opp), moves) in &self.entries {
+            text.push_str(&Board::from_sides(*me, *opp).to_notation());
+            for (_move, weight) in moves {
+                let _ = write!(text, " {}:{}", bitboard_functions::to_cell(*_move), weight);
+            }
+            text.push('\n');
+        }
+        fs::write(path, text).map_err(|e| Error::io(path, e))
+    }
+
+    /// The function `canonical` returns the canonical form of a position and the index in
+    /// `SYMMETRIES` of a symmetry sending the position to it.
+    fn canonical(me: i16, opp: i16) -> (usize, (i16, i16)) {
+        let position = bitboard_functions::canonical(me, opp);
+        let symmetry = (0..SYMMETRIES.len())
+            .find(|symmetry| (bitboard_functions::transform(me, *symmetry), bitboard_functions::transform(opp, *symmetry)) == position)
+            .unwrap_or(0);
+        (symmetry, position)
+    }
+}
+
+/// The function `self_play` plays the games `OpeningBook::from_games` aggregates.
+///
+/// Arguments:
+///
+/// * `x`: The `x` parameter is the player moving first.
+/// * `o`: The `o` parameter is the player moving second, usually a copy of `x`.
+/// * `games`: The `games` parameter is the number of games to play.
+/// * `exploration`: The `exploration` parameter is the probability of a random move, which makes the
+/// games differ.
+/// * `rng`: The `rng` parameter draws the random moves.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) fn self_play(x: Box<dyn Player>, o: Box<dyn Player>, games: u32, exploration: f64, rng: &mut ChaCha8Rng) -> Vec<MatchRecord> {
+    let mut x = Exploring { player: x, exploration };
+    let mut o = Exploring { player: o, exploration };
+    (0..games).map(|_| player::play_match(&mut x, &mut o, &[], None, rng)).collect()
+}
+
+/// The struct `Booked` makes a player play from an opening book while the game is young.
+///
+/// Properties:
+///
+/// * `player`: The `player` property is the player taking over once the book runs out.
+/// * `book`: The `book` property is the opening book, shared between players.
+/// * `plies`: The `plies` property is the number of plies of a game the book is consulted for.
+pub(crate) struct Booked<P> {
+    pub player: P,
+    pub book: Arc<OpeningBook>,
+    pub plies: u8,
+}
+
+impl<P: Player> Player for Booked<P> {
+    fn choose_move(&mut self, board: &Board, clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        let (me, opp) = board.get_sides();
+        if (me | opp).count_ones() < self.plies as u32 {
+            if let Some(_move) = self.book.choose(board, rng) {
+                return Some(_move);
+            }
+        }
+        self.player.choose_move(board, clock, rng)
+    }
+
+    fn observe_result(&mut self, score: f64) {
+        self.player.observe_result(score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// The sorted moves of a position in the book, without their weights.
+    fn book_moves(book: &OpeningBook, board: &Board) -> Vec<i16> {
+        let mut moves: Vec<i16> = book.moves(board).into_iter().map(|(_move, _)| _move).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let book = OpeningBook::from_solver(4);
+        let path = env::temp_dir().join(format!("acordion-book-test-{}.txt", process::id()));
+        book.save(&path).unwrap();
+        let loaded = OpeningBook::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.len(), book.len());
+        assert_eq!(loaded.entries, book.entries);
+    }
+
+    #[test]
+    fn moves_are_mapped_back_through_the_symmetry() {
+        let mut book = OpeningBook::default();
+        // No symmetry but the identity leaves this position in place, so each image has one move.
+        let board = Board::from_notation("OX.......").unwrap();
+        book.add(&board, 1 << 5, 3);
+        let (me, opp) = board.get_sides();
+        for symmetry in 0..SYMMETRIES.len() {
+            let image = Board::from_sides(bitboard_functions::transform(me, symmetry), bitboard_functions::transform(opp, symmetry));
+            assert_eq!(book.moves(&image), vec![(bitboard_functions::transform(1 << 5, symmetry), 3)], "symmetry {}", symmetry);
+        }
+    }
+
+    #[test]
+    fn the_solver_book_holds_the_best_moves_of_every_image() {
+        let book = OpeningBook::from_solver(3);
+        let mut solver = Solver::new();
+        let mut positions = vec![Board::new()];
+        for _ in 0..3 {
+            let mut next = Vec::new();
+            for board in positions {
+                let mut best = solver.best_moves(&board);
+                best.sort();
+                assert_eq!(book_moves(&book, &board), best, "{}", board.to_notation());
+                for _move in bitboard_functions::get_indi_bits(board.clone().gen_move()) {
+                    let mut child = board.clone();
+                    child.play_move(_move);
+                    next.push(child);
+                }
+            }
+            positions = next;
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{config::TrainerConfig, curriculum::Stage, player::Expert, pool::Sampling, tablebase::MAX_SIDE, tournament::AgentSpec};
+
+/// The run directory used when neither `--workspace` nor `ACORDION_WORKSPACE` is given.
+const DEFAULT_WORKSPACE: &str = "runs/default";
+
+/// Train and play a neural network tic-tac-toe agent.
+#[derive(Parser)]
+#[command(version, about)]
+pub(crate) struct Cli {
+    /// Run directory holding the trainee and the opponent pools.
+    #[arg(long, global = true, env = "ACORDION_WORKSPACE", default_value = DEFAULT_WORKSPACE)]
+    pub workspace: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// Train the trainee against its opponent pool.
+    Train(TrainArgs),
+    /// Play against the trainee in the terminal.
+    Play {
+        /// Side you play in the first game; `s` swaps sides during the session.
+        #[arg(long, value_enum, default_value = "x")]
+        side: Side,
+        /// Play a single game on the standard input instead of the full-screen board.
+        #[arg(long)]
+        plain: bool,
+        /// Opening book the trainee plays from in a --plain game, as written by `book`.
+        #[arg(long, requires = "plain")]
+        book: Option<PathBuf>,
+        /// Plies of the game the opening book is consulted for.
+        #[arg(long, default_value_t = 4)]
+        book_plies: u8,
+    },
+    /// Show how the trainee scores the moves of a position.
+    Eval {
+        /// Position in row-major notation, e.g. `X.O.X....`; the empty board by default.
+        #[arg(default_value = ".........")]
+        position: String,
+        /// Deepest search past each legal move; 0 scores them with the network alone. Defaults to 0,
+        /// or to no limit when --nodes or --movetime is given.
+        #[arg(long)]
+        depth: Option<u8>,
+        /// Stop deepening the search after visiting this many positions.
+        #[arg(long)]
+        nodes: Option<u64>,
+        /// Stop deepening the search after this many milliseconds.
+        #[arg(long)]
+        movetime: Option<u64>,
+        /// Endgame tablebase scoring the leaves of the search it covers, as written by `tablebase`;
+        /// defaults to `tablebase.bin` in the workspace, if there is one.
+        #[arg(long)]
+        tablebase: Option<PathBuf>,
+    },
+    /// Let the trainee play a game against itself.
+    Selfplay(BookOptions),
+    /// Solve a position with the perfect solver.
+    Solve {
+        /// Position in row-major notation, e.g. `X.O.X....`; the empty board by default.
+        #[arg(default_value = ".........")]
+        position: String,
+    },
+    /// Measure how often the trainee plays optimally, against the perfect solver.
+    Accuracy,
+    /// Count the positions the search visits with and without move ordering.
+    Bench {
+        /// Deepest search past each legal move; the whole game tree by default.
+        #[arg(long)]
+        depth: Option<u8>,
+    },
+    /// Print the Elo leaderboard of the workspace.
+    Ratings,
+    /// Check an agent against the random and the perfect player.
+    Gauntlet(GauntletArgs),
+    /// Play a round robin between agents and print the cross table.
+    Tournament(TournamentArgs),
+    /// Build an opening book from the perfect solver or from self-play of the trainee.
+    Book(BookArgs),
+    /// Solve every position of an m,n,k game with few empty cells into an endgame tablebase.
+    Tablebase {
+        /// Rows of the board.
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=MAX_SIDE as i64))]
+        rows: u8,
+        /// Columns of the board.
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=MAX_SIDE as i64))]
+        cols: u8,
+        /// Stones in a row, column or diagonal that win.
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=MAX_SIDE as i64))]
+        k: u8,
+        /// Largest number of empty cells of the positions solved.
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(0..=(MAX_SIDE as i64).pow(2)))]
+        empty: u8,
+        /// File the tablebase is written to; defaults to `tablebase.bin` in the workspace for the 3x3
+        /// board and is required for other boards, whose tables the agents cannot probe.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Copy the trainee out of the workspace.
+    Export {
+        /// File the trainee is written to.
+        output: PathBuf,
+        /// Write the network as JSON instead of the binary `.flow` format.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// The side played by the user.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Side {
+    X,
+    O,
+}
+
+/// Options of `tournament`.
+#[derive(Args)]
+pub(crate) struct TournamentArgs {
+    /// Entrants: trainee, snapshot:<id>, file:<path>, random, solver, mcts[:<simulations>],
+    /// heuristic[:<skill>], noisy[:<accuracy>], qlearning[:<episodes>] or search[:<depth>]. A
+    /// network followed by @<limits> searches within them, e.g. snapshot:3@depth=4,nodes=5000.
+    #[arg(required = true, num_args = 2..)]
+    pub agents: Vec<AgentSpec>,
+    /// Game pairs per pairing; each pair plays the same opening with both colors.
+    #[arg(long, default_value_t = 10)]
+    pub games: u32,
+    /// Random moves opening each game pair.
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub random_plies: u8,
+    /// Seed of the openings and of the random choices of the agents.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Do not add the games to the ratings of the workspace.
+    #[arg(long)]
+    pub unrated: bool,
+    #[command(flatten)]
+    pub book: BookOptions,
+}
+
+/// Options for playing from an opening book.
+#[derive(Args)]
+pub(crate) struct BookOptions {
+    /// Opening book the players consult before thinking, as written by `book`.
+    #[arg(long)]
+    pub book: Option<PathBuf>,
+    /// Plies of each game the opening book is consulted for.
+    #[arg(long, default_value_t = 4)]
+    pub book_plies: u8,
+}
+
+/// Options of `book`.
+#[derive(Args)]
+pub(crate) struct BookArgs {
+    /// Where the moves of the book come from.
+    #[arg(long, value_enum, default_value = "solver")]
+    pub source: BookSource,
+    /// Plies of each game the book covers.
+    #[arg(long, default_value_t = 4)]
+    pub plies: u8,
+    /// Self-play games aggregated into the book.
+    #[arg(long, default_value_t = 1000)]
+    pub games: u32,
+    /// Probability of a random move in the self-play games.
+    #[arg(long, default_value_t = 0.3)]
+    pub exploration: f64,
+    /// Seed of the self-play games.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// File the book is written to; defaults to `book.txt` in the workspace.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// The source of an opening book.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum BookSource {
+    /// Every best move of the perfect solver.
+    Solver,
+    /// The moves of the trainee's self-play games, weighted by their results.
+    Selfplay,
+}
+
+/// Options of `gauntlet`.
+#[derive(Args)]
+pub(crate) struct GauntletArgs {
+    /// Agent under test, in the notation of `tournament`.
+    #[arg(default_value = "trainee")]
+    pub agent: AgentSpec,
+    /// Games per opponent and color.
+    #[arg(long, default_value_t = 100)]
+    pub games: u32,
+    /// Lowest share of games that must be won against the random player.
+    #[arg(long, default_value_t = 0.5)]
+    pub min_random_wins: f64,
+    /// Most games that may be lost against the perfect player.
+    #[arg(long, default_value_t = 0)]
+    pub max_solver_losses: u32,
+    /// Seed of the random choices of the agents.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+/// Hyperparameters of `train`. Values given here override the configuration file.
+#[derive(Args)]
+pub(crate) struct TrainArgs {
+    /// Continue the interrupted run of the workspace instead of starting a new one.
+    #[arg(long, conflicts_with = "config")]
+    pub resume: bool,
+    /// Start a new run even if the workspace holds an interrupted one, which is then discarded.
+    #[arg(long, conflicts_with = "resume")]
+    pub force: bool,
+    /// Show a live dashboard of the run in the terminal.
+    #[arg(long)]
+    pub dashboard: bool,
+    /// TOML file with the trainer configuration; defaults to `trainer.toml` in the workspace.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Maximum number of snapshots in the opponent pool.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub size: Option<i16>,
+    /// Number of training iterations.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub iterations: Option<i16>,
+    /// Iterations between two snapshots of the trainee.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub ghost_steps: Option<i16>,
+    /// Opponents drawn from the pool per iteration.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub opponents: Option<i16>,
+    /// How opponents are drawn from the pool.
+    #[arg(long, value_enum)]
+    pub sampling: Option<Sampling>,
+    /// Learning rate of the trainee network.
+    #[arg(long)]
+    pub learning_rate: Option<f64>,
+    /// Discount factor applied per move to the rewards.
+    #[arg(long)]
+    pub discount: Option<f64>,
+    /// Samples fitted after each game.
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    pub epochs: Option<i64>,
+    /// Game pairs played against each opponent per iteration.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub games_per_opponent: Option<i16>,
+    /// Probability of playing a random move during self-play.
+    #[arg(long)]
+    pub exploration: Option<f64>,
+    /// Iterations between two evaluations against the perfect solver; 0 turns them off.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(0..))]
+    pub eval_interval: Option<i16>,
+    /// Also play the rule-based heuristic player each iteration, following its rules with this
+    /// probability.
+    #[arg(long)]
+    pub heuristic_opponent: Option<f64>,
+    /// Also play the random player each iteration; `--random-opponent false` turns off a configuration
+    /// file that enables it.
+    #[arg(long, value_name = "true|false", num_args = 0..=1, default_missing_value = "true")]
+    pub random_opponent: Option<bool>,
+    /// Also play a noisy expert each iteration, playing the expert's move with this probability and a
+    /// random one otherwise.
+    #[arg(long)]
+    pub noisy_expert_opponent: Option<f64>,
+    /// Player followed by the noisy expert.
+    #[arg(long, value_enum)]
+    pub noisy_expert: Option<Expert>,
+    /// Stages of the curriculum, separated by commas, e.g. `random,noisy-expert,pool,search`.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub curriculum: Vec<Stage>,
+    /// Score, draws counting half, against a stage of the curriculum needed to move on to the next one.
+    #[arg(long)]
+    pub promotion_threshold: Option<f64>,
+    /// Most recent games of a stage the score is measured over.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub promotion_games: Option<i16>,
+    /// Moves searched ahead by the trainee when it is the opponent of the search stage.
+    #[arg(long)]
+    pub search_depth: Option<u8>,
+    /// Iterations between two matches of the candidate against the champion; 0 turns gating off.
+    #[arg(long, value_parser = clap::value_parser!(i16).range(0..))]
+    pub gate_interval: Option<i16>,
+    /// Game pairs of a gate match.
+    #[arg(long)]
+    pub gate_games: Option<u32>,
+    /// Score the candidate must exceed to replace the champion.
+    #[arg(long)]
+    pub gate_threshold: Option<f64>,
+    /// Seed of the random generator, for repeatable runs.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+impl Cli {
+    /// The function `dashboard` tells whether the training dashboard was asked for, in which case the
+    /// dashboard takes over the log output.
+    pub fn dashboard(&self) -> bool {
+        matches!(&self.command, Command::Train(args) if args.dashboard)
+    }
+}
+
+impl TrainArgs {
+    /// The function `apply` overrides the fields of a configuration with the values given on the
+    /// command line.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The `config` parameter is the configuration read from file or the default one.
+    pub fn apply(&self, mut config: TrainerConfig) -> TrainerConfig {
+        if let Some(size) = self.size {
+            config.size = size;
+        }
+        if let Some(iterations) = self.iterations {
+            config.iterations = iterations;
+        }
+        if let Some(ghost_steps) = self.ghost_steps {
+            config.ghost_steps = ghost_steps;
+        }
+        if let Some(opponents) = self.opponents {
+            config.opponents = opponents;
+        }
+        if let Some(sampling) = self.sampling {
+            config.sampling = sampling;
+        }
+        if let Some(learning_rate) = self.learning_rate {
+            config.learning_rate = learning_rate;
+        }
+        if let Some(discount) = self.discount {
+            config.discount_factor = discount;
+        }
+        if let Some(epochs) = self.epochs {
+            config.epochs = epochs;
+        }
+        if let Some(games_per_opponent) = self.games_per_opponent {
+            config.games_per_opponent = games_per_opponent;
+        }
+        if let Some(exploration) = self.exploration {
+            config.exploration = exploration;
+        }
+        if let Some(eval_interval) = self.eval_interval {
+            config.eval_interval = eval_interval;
+        }
+        if let Some(skill) = self.heuristic_opponent {
+            config.heuristic_opponent = Some(skill);
+        }
+        if let Some(random_opponent) = self.random_opponent {
+            config.random_opponent = random_opponent;
+        }
+        if let Some(accuracy) = self.noisy_expert_opponent {
+            config.noisy_expert_opponent = Some(accuracy);
+        }
+        if let Some(expert) = self.noisy_expert {
+            config.noisy_expert = expert;
+        }
+        if !self.curriculum.is_empty() {
+            config.curriculum = self.curriculum.clone();
+        }
+        if let Some(threshold) = self.promotion_threshold {
+            config.promotion_threshold = threshold;
+        }
+        if let Some(games) = self.promotion_games {
+            config.promotion_games = games;
+        }
+        if let Some(depth) = self.search_depth {
+            config.search_depth = depth;
+        }
+        if let Some(interval) = self.gate_interval {
+            config.gate_interval = interval;
+        }
+        if let Some(games) = self.gate_games {
+            config.gate_games = games;
+        }
+        if let Some(threshold) = self.gate_threshold {
+            config.gate_threshold = threshold;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        config
+    }
+}
@@ -0,0 +1,176 @@
+use std::{fs, path::Path};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{curriculum::Stage, error::{Error, Result}, player::Expert, pool::Sampling};
+
+/// The struct `TrainerConfig` holds the hyperparameters of a training run. It is read from a TOML
+/// file in which every key is optional, missing keys keeping their default value.
+///
+/// Properties:
+///
+/// * `size`: The `size` property is the maximum number of snapshots kept in the opponent pool.
+/// * `iterations`: The `iterations` property is the number of training iterations to run.
+/// * `ghost_steps`: The `ghost_steps` property is the number of iterations between two snapshots of
+/// the trainee.
+/// * `opponents`: The `opponents` property is the number of opponents drawn from the pool per
+/// iteration.
+/// * `sampling`: The `sampling` property is the rule opponents are drawn by: `uniform`, `recency` or
+/// `win-rate`.
+/// * `learning_rate`: The `learning_rate` property is the learning rate of the trainee network.
+/// * `discount_factor`: The `discount_factor` property is the factor applied per move when rewards are
+/// propagated back through a game.
+/// * `epochs`: The `epochs` property is the number of samples the network is fitted on after each
+/// game.
+/// * `games_per_opponent`: The `games_per_opponent` property is the number of game pairs played
+/// against each opponent per iteration.
+/// * `exploration`: The `exploration` property is the probability that a player makes a random move
+/// instead of its best one, which is what makes repeated games against the same opponent differ.
+/// * `eval_interval`: The `eval_interval` property is the number of iterations between two
+/// evaluations of the trainee against the perfect solver; `0` turns them off.
+/// * `heuristic_opponent`: The `heuristic_opponent` property is the skill of the rule-based
+/// `Heuristic` player, between 0 and 1, that the trainee plays each iteration besides the snapshots
+/// drawn from the pool; it is left out when absent.
+/// * `random_opponent`: The `random_opponent` property makes the trainee also play the random player
+/// each iteration.
+/// * `noisy_expert_opponent`: The `noisy_expert_opponent` property is the accuracy of the noisy expert
+/// the trainee plays each iteration, the probability that it plays the move of `noisy_expert` rather
+/// than a random one; it is left out when absent.
+/// * `noisy_expert`: The `noisy_expert` property is the player the noisy expert follows: `solver` or
+/// `heuristic`.
+/// * `curriculum`: The `curriculum` property lists the stages the trainee goes through, e.g. `random`,
+/// `noisy-expert`, `heuristic`, `pool` and `search`; the opponents of the current stage replace the
+/// snapshots drawn from the pool. Training plays the pool alone when it is empty.
+/// * `promotion_threshold`: The `promotion_threshold` property is the score, draws counting half, the
+/// trainee needs against the current stage to be promoted to the next one.
+/// * `promotion_games`: The `promotion_games` property is the number of most recent games of a stage
+/// the score is measured over.
+/// * `search_depth`: The `search_depth` property is how many moves ahead the trainee searches when it
+/// is the opponent of the `search` stage.
+/// * `gate_interval`: The `gate_interval` property is the number of iterations between two matches of
+/// the candidate being trained against the champion, the trainee of the workspace, which it only
+/// replaces when it wins the match; `0` turns gating off and trains the trainee directly.
+/// * `gate_games`: The `gate_games` property is the number of game pairs of a gate match.
+/// * `gate_threshold`: The `gate_threshold` property is the score, draws counting half, the candidate
+/// must exceed to replace the champion.
+/// * `seed`: The `seed` property seeds the random generator of the trainer, making runs repeatable.
+/// A fresh seed is drawn when it is absent.
+/// * `reset_steps`: The `reset_steps` property is no longer used, since the opponent pool replaced the
+/// trainers it refreshed. It is still read, and ignored, so that older configuration files load.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct TrainerConfig {
+    pub size: i16,
+    pub iterations: i16,
+    pub ghost_steps: i16,
+    pub opponents: i16,
+    pub sampling: Sampling,
+    pub learning_rate: f64,
+    pub discount_factor: f64,
+    pub epochs: i64,
+    pub games_per_opponent: i16,
+    pub exploration: f64,
+    pub eval_interval: i16,
+    pub heuristic_opponent: Option<f64>,
+    pub random_opponent: bool,
+    pub noisy_expert_opponent: Option<f64>,
+    pub noisy_expert: Expert,
+    pub curriculum: Vec<Stage>,
+    pub promotion_threshold: f64,
+    pub promotion_games: i16,
+    pub search_depth: u8,
+    pub gate_interval: i16,
+    pub gate_games: u32,
+    pub gate_threshold: f64,
+    pub seed: Option<u64>,
+    #[serde(skip_serializing)]
+    pub reset_steps: Option<i16>,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> Self {
+        TrainerConfig {
+            size: 8,
+            iterations: 10000,
+            ghost_steps: 2,
+            opponents: 2,
+            sampling: Sampling::Uniform,
+            learning_rate: 0.01,
+            discount_factor: 0.99,
+            epochs: 50_000,
+            games_per_opponent: 1,
+            exploration: 0.0,
+            eval_interval: 1,
+            heuristic_opponent: None,
+            random_opponent: false,
+            noisy_expert_opponent: None,
+            noisy_expert: Expert::Solver,
+            curriculum: Vec::new(),
+            promotion_threshold: 0.6,
+            promotion_games: 20,
+            search_depth: 2,
+            gate_interval: 0,
+            gate_games: 20,
+            gate_threshold: 0.55,
+            seed: None,
+            reset_steps: None,
+        }
+    }
+}
+
+impl TrainerConfig {
+    /// The function `load` reads a configuration from a TOML file.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the TOML file to be read.
+    ///
+    /// Returns:
+    ///
+    /// The parsed `TrainerConfig`, or an `Error::Config` if the file is not a valid configuration.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let config: TrainerConfig = toml::from_str(&text).map_err(|source| Error::Config { path: path.to_path_buf(), source })?;
+        if config.reset_steps.is_some() {
+            warn!("{}: `reset_steps` is no longer used and is ignored", path.display());
+        }
+        Ok(config)
+    }
+
+    /// The function `validate` checks that the trainee can be promoted from every stage of the
+    /// curriculum but the last. Against an unbeatable stage the trainee scores at most 0.5, so a higher
+    /// `promotion_threshold` would keep it there forever. The `pool` and `search` stages are versions of
+    /// the trainee itself, against which it usually scores about 0.5, so a higher threshold there only
+    /// raises a warning.
+    ///
+    /// Returns:
+    ///
+    /// `Ok(())`, or an `Error::Curriculum` naming the first stage that cannot be left.
+    pub fn validate(&self) -> Result<()> {
+        let Some((_, stages)) = self.curriculum.split_last() else {
+            return Ok(());
+        };
+        if self.promotion_threshold <= 0.5 {
+            return Ok(());
+        }
+        for stage in stages {
+            if stage.is_unbeatable(self) {
+                return Err(Error::Curriculum {
+                    message: format!(
+                        "the {} stage plays perfectly and can at best be drawn against, so the trainee never reaches the promotion_threshold of {}; lower it to 0.5, weaken the stage or move it last",
+                        stage, self.promotion_threshold
+                    ),
+                });
+            }
+            if matches!(stage, Stage::Pool | Stage::Search) {
+                warn!(
+                    "The trainee usually scores about 0.5 against the {} stage, so it may never reach the promotion_threshold of {}",
+                    stage, self.promotion_threshold
+                );
+            }
+        }
+        Ok(())
+    }
+}
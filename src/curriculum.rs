@@ -0,0 +1,124 @@
+use std::{collections::VecDeque, fmt};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::TrainerConfig, metrics::{GameMetrics, PromotionMetrics}, trainer::Opponent};
+
+/// The enum `Stage` is a step of the curriculum: the opponents the trainee plays until it beats them
+/// often enough to move on.
+///
+/// Variants:
+///
+/// * `Random`: The random player.
+/// * `NoisyExpert`: The noisy expert of `noisy_expert`.
+/// * `Heuristic`: The rule-based `Heuristic` player, with the skill of `heuristic_opponent`.
+/// * `Pool`: Snapshots drawn from the opponent pool.
+/// * `Search`: The trainee itself, searching `search_depth` moves ahead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Stage {
+    Random,
+    NoisyExpert,
+    Heuristic,
+    Pool,
+    Search,
+}
+
+impl Stage {
+    /// The function `is_unbeatable` tells whether the opponent of the stage plays perfectly, so that
+    /// the trainee scores at most 0.5 against it: the `heuristic` stage at a skill of 1 and the
+    /// `noisy-expert` stage at an accuracy of 1.
+    pub fn is_unbeatable(self, config: &TrainerConfig) -> bool {
+        match self {
+            Stage::Heuristic => config.heuristic_opponent.unwrap_or(1.0) >= 1.0,
+            Stage::NoisyExpert => config.noisy_expert_opponent.is_some_and(|accuracy| accuracy >= 1.0),
+            Stage::Random | Stage::Pool | Stage::Search => false,
+        }
+    }
+
+    /// The function `includes` tells whether a game against an opponent belongs to the stage.
+    pub fn includes(self, opponent: Opponent) -> bool {
+        matches!(
+            (self, opponent),
+            (Stage::Random, Opponent::Random)
+                | (Stage::NoisyExpert, Opponent::NoisyExpert)
+                | (Stage::Heuristic, Opponent::Heuristic)
+                | (Stage::Pool, Opponent::Snapshot(_))
+                | (Stage::Search, Opponent::Search)
+        )
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Stage::Random => "random",
+            Stage::NoisyExpert => "noisy-expert",
+            Stage::Heuristic => "heuristic",
+            Stage::Pool => "pool",
+            Stage::Search => "search",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The struct `Curriculum` is the progress of a run through the stages of its `curriculum`. It is
+/// saved with the state of the run so that a resumed run stays at its stage.
+///
+/// Properties:
+///
+/// * `stage`: The `stage` property is the index of the current stage.
+/// * `scores`: The `scores` property holds the trainee's scores, 1, 0.5 or 0, in the last
+/// `promotion_games` games of the current stage.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Curriculum {
+    stage: usize,
+    scores: VecDeque<f64>,
+}
+
+impl Curriculum {
+    /// The function `stage` returns the current stage, or `None` when the run has no curriculum.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The `config` parameter is the configuration of the run, holding its stages.
+    pub fn stage(&self, config: &TrainerConfig) -> Option<Stage> {
+        config.curriculum.get(self.stage.min(config.curriculum.len().saturating_sub(1))).copied()
+    }
+
+    /// The function `record` adds the games of an iteration to the current stage and moves on to the
+    /// next stage once the trainee's score over the last `promotion_games` games, draws counting half,
+    /// reaches `promotion_threshold`. The last stage is never left.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The `config` parameter is the configuration of the run.
+    /// * `iteration`: The `iteration` parameter is the iteration the games were played in.
+    /// * `games`: The `games` parameter holds the games of the iteration; those against opponents of
+    /// other stages are ignored.
+    ///
+    /// Returns:
+    ///
+    /// The promotion, if the trainee was promoted.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn record(&mut self, config: &TrainerConfig, iteration: i16, games: &[GameMetrics]) -> Option<PromotionMetrics> {
+        let stage = self.stage(config)?;
+        let window = config.promotion_games.max(1) as usize;
+        for game in games.iter().filter(|game| stage.includes(game.opponent)) {
+            self.scores.push_back((game.result + 1) as f64 / 2.0);
+            if self.scores.len() > window {
+                self.scores.pop_front();
+            }
+        }
+        let next = *config.curriculum.get(self.stage + 1)?;
+        let score = self.scores.iter().sum::<f64>() / self.scores.len().max(1) as f64;
+        if self.scores.len() < window || score < config.promotion_threshold {
+            return None;
+        }
+        self.stage += 1;
+        self.scores.clear();
+        Some(PromotionMetrics { iteration, from: stage, to: next, score })
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Write},
+    panic,
+    sync::{atomic::Ordering, mpsc::{self, Receiver, Sender}},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{enable_raw_mode, EnterAlternateScreen},
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, Paragraph, Row, Sparkline, Table},
+    DefaultTerminal, Frame, Terminal,
+};
+
+use crate::{error::{Error, Result}, metrics::{GameMetrics, IterationMetrics, Metric}, trainer::{Opponent, Trainer}};
+
+/// The number of log lines kept on screen.
+const LOG_LINES: usize = 200;
+/// How long the dashboard waits for a key press before redrawing.
+const FRAME: Duration = Duration::from_millis(100);
+
+/// The enum `Update` is a message from the training thread to the dashboard.
+///
+/// Variants:
+///
+/// * `Metric`: A game or iteration finished.
+/// * `Log`: A line was written through `log`.
+/// * `Finished`: Training returned, successfully or not, or panicked.
+enum Update {
+    Metric(Metric),
+    Log(String),
+    Finished,
+}
+
+/// The struct `FinishGuard` sends `Update::Finished` when it is dropped, so that the dashboard also
+/// closes when training panics.
+struct FinishGuard(Sender<Update>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(Update::Finished);
+    }
+}
+
+/// The struct `LogPipe` is the target of `env_logger` while the dashboard is open: it forwards every
+/// line to the dashboard instead of writing over the screen.
+struct LogPipe {
+    updates: Sender<Update>,
+    line: Vec<u8>,
+}
+
+impl Write for LogPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            if *byte == b'\n' {
+                let line = String::from_utf8_lossy(&self.line).into_owned();
+                let _ = self.updates.send(Update::Log(line));
+                self.line.clear();
+            } else {
+                self.line.push(*byte);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The struct `Record` counts the trainee's games against one snapshot.
+#[derive(Default)]
+struct Record {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+/// The struct `Dashboard` is what the dashboard knows about the run.
+///
+/// Properties:
+///
+/// * `iterations`: The `iterations` property is the number of iterations of the run.
+/// * `latest`: The `latest` property holds the metrics of the last completed iteration.
+/// * `last_game`: The `last_game` property holds the metrics of the last game played.
+/// * `losses`: The `losses` property is the history of the mean training loss after fitting.
+/// * `accuracy`: The `accuracy` property is the history of the fraction of optimal moves.
+/// * `opponents`: The `opponents` property counts the trainee's results against each opponent.
+/// * `log`: The `log` property holds the latest log lines.
+/// * `stopping`: The `stopping` property is set once the user asked training to stop.
+struct Dashboard {
+    iterations: i16,
+    latest: Option<IterationMetrics>,
+    last_game: Option<GameMetrics>,
+    losses: Vec<f64>,
+    accuracy: Vec<f64>,
+    opponents: BTreeMap<Opponent, Record>,
+    log: VecDeque<String>,
+    stopping: bool,
+}
+
+/// The function `run` trains with a live terminal dashboard. Training runs on its own thread while
+/// this one draws; pressing `q` stops training after the current iteration.
+///
+/// Arguments:
+///
+/// * `trainer`: The `trainer` parameter is the trainer to run.
+/// * `job`: The `job` parameter starts training, e.g. by calling `Trainer::train` or `Trainer::resume`.
+///
+/// Returns:
+///
+/// The result of `job`, an `Error::Panic` if it panicked, or the error raised while setting up the
+/// terminal.
+pub(crate) fn run(mut trainer: Trainer, job: impl FnOnce(&mut Trainer) -> Result<()> + Send + 'static) -> Result<()> {
+    let (updates, receiver) = mpsc::channel();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .target(env_logger::Target::Pipe(Box::new(LogPipe { updates: updates.clone(), line: Vec::new() })))
+        .init();
+
+    let (metrics, metric_receiver) = mpsc::channel();
+    trainer.observe(metrics);
+    let stop = trainer.stop_handle();
+    let mut dashboard = Dashboard {
+        iterations: trainer.config().iterations,
+        latest: None,
+        last_game: None,
+        losses: Vec::new(),
+        accuracy: Vec::new(),
+        opponents: BTreeMap::new(),
+        log: VecDeque::new(),
+        stopping: false,
+    };
+
+    let mut terminal = open_terminal().map_err(Error::terminal)?;
+    let forward = updates.clone();
+    thread::spawn(move || {
+        for metric in metric_receiver {
+            if forward.send(Update::Metric(metric)).is_err() {
+                break;
+            }
+        }
+    });
+    let training = thread::spawn(move || {
+        let _finished = FinishGuard(updates);
+        job(&mut trainer)
+    });
+
+    let drawn = dashboard.show(&mut terminal, &receiver, || stop.store(true, Ordering::Relaxed));
+    ratatui::restore();
+    if let Err(source) = drawn {
+        // Stop training at the end of the iteration, so that it can be resumed, before reporting.
+        stop.store(true, Ordering::Relaxed);
+        let _ = training.join();
+        return Err(Error::terminal(source));
+    }
+
+    let result = training.join().unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_string());
+        Err(Error::Panic { message })
+    });
+    for line in receiver.try_iter().filter_map(|update| match update {
+        Update::Log(line) => Some(line),
+        _ => None,
+    }) {
+        dashboard.log.push_back(line);
+    }
+    for line in dashboard.log.iter().rev().take(5).collect::<Vec<_>>().into_iter().rev() {
+        eprintln!("{}", line);
+    }
+    result
+}
+
+/// The function `open_terminal` switches the terminal to raw mode and the alternate screen, like
+/// `ratatui::try_init`, but restores it on a panic only if the panic happens on the calling thread.
+/// A panic in training is reported by `run` instead: restoring the terminal under the dashboard while
+/// it still waits for key presses would leave it waiting forever.
+///
+/// Returns:
+///
+/// The terminal to draw on, or the error raised while setting it up.
+fn open_terminal() -> io::Result<DefaultTerminal> {
+    let dashboard = thread::current().id();
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() == dashboard {
+            ratatui::restore();
+        }
+        hook(info);
+    }));
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+impl Dashboard {
+    /// The function `show` draws the dashboard until training finishes.
+    ///
+    /// Arguments:
+    ///
+    /// * `terminal`: The `terminal` parameter is the terminal drawn on.
+    /// * `receiver`: The `receiver` parameter delivers the updates of the training thread.
+    /// * `stop`: The `stop` parameter asks training to stop; it is called when `q` is pressed.
+    fn show(&mut self, terminal: &mut DefaultTerminal, receiver: &Receiver<Update>, stop: impl Fn()) -> io::Result<()> {
+        let started = Instant::now();
+        loop {
+            for update in receiver.try_iter() {
+                match update {
+                    Update::Metric(metric) => self.record(metric),
+                    Update::Log(line) => {
+                        self.log.push_back(line);
+                        if self.log.len() > LOG_LINES {
+                            self.log.pop_front();
+                        }
+                    }
+                    Update::Finished => return Ok(()),
+                }
+            }
+            terminal.draw(|frame| self.draw(frame, started.elapsed()))?;
+
+            if event::poll(FRAME)? {
+                if let Event::Key(key) = event::read()? {
+                    let interrupt = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if key.kind == KeyEventKind::Press && (key.code == KeyCode::Char('q') || key.code == KeyCode::Esc || interrupt) {
+                        self.stopping = true;
+                        stop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The function `record` adds a metric to the state of the dashboard.
+    fn record(&mut self, metric: Metric) {
+        match metric {
+            Metric::Game(game) => {
+                let record = self.opponents.entry(game.opponent).or_default();
+                match game.result {
+                    1 => record.wins += 1,
+                    0 => record.draws += 1,
+                    _ => record.losses += 1,
+                }
+                self.last_game = Some(game);
+            }
+            Metric::Iteration(iteration) => {
+                self.losses.push(iteration.loss_after);
+                if let Some(rate) = iteration.optimal_rate {
+                    self.accuracy.push(rate);
+                }
+                self.latest = Some(iteration);
+            }
+            // Promotions and gates reach the dashboard through the log.
+            Metric::Promotion(_) | Metric::Gate(_) => {}
+        }
+    }
+
+    /// The function `draw` renders the dashboard.
+    fn draw(&self, frame: &mut Frame, elapsed: Duration) {
+        let [header, middle, charts, log, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(9),
+            Constraint::Length(7),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        self.draw_progress(frame, header, elapsed);
+
+        let [pool, stats, game] = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(35), Constraint::Percentage(25)]).areas(middle);
+        self.draw_pool(frame, pool);
+        self.draw_stats(frame, stats);
+        self.draw_game(frame, game);
+
+        let [losses, accuracy] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(charts);
+        let loss = self.losses.last().map(|loss| format!(" {:.4}", loss)).unwrap_or_default();
+        draw_sparkline(frame, losses, &format!("Training loss{}", loss), &self.losses, Color::Yellow);
+        let rate = self.accuracy.last().map(|rate| format!(" {:.1}%", 100.0 * rate)).unwrap_or_default();
+        draw_sparkline(frame, accuracy, &format!("Optimal moves{}", rate), &self.accuracy, Color::Green);
+
+        let lines: Vec<Line> = self
+            .log
+            .iter()
+            .rev()
+            .take(log.height.saturating_sub(2) as usize)
+            .rev()
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Log")), log);
+
+        let help = if self.stopping {
+            "Stopping after the current iteration..."
+        } else {
+            "q: stop after the current iteration (continue later with `train --resume`)"
+        };
+        frame.render_widget(Paragraph::new(help).style(Style::default().add_modifier(Modifier::DIM)), footer);
+    }
+
+    /// The function `draw_progress` renders the iteration gauge.
+    fn draw_progress(&self, frame: &mut Frame, area: Rect, elapsed: Duration) {
+        let done = self.latest.as_ref().map_or(0, |latest| latest.iteration as i32 + 1);
+        let total = self.iterations.max(1) as i32;
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Training"))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio((done as f64 / total as f64).clamp(0.0, 1.0))
+            .label(format!("iteration {}/{}  {}s", done, total, elapsed.as_secs()));
+        frame.render_widget(gauge, area);
+    }
+
+    /// The function `draw_pool` renders the trainee's record against each opponent.
+    fn draw_pool(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.opponents.iter().rev().map(|(opponent, record)| {
+            let games = (record.wins + record.draws + record.losses).max(1) as f64;
+            Row::new(vec![
+                opponent.to_string(),
+                record.wins.to_string(),
+                record.draws.to_string(),
+                record.losses.to_string(),
+                format!("{:.0}%", 100.0 * (record.wins as f64 + 0.5 * record.draws as f64) / games),
+            ])
+        });
+        let widths = [Constraint::Min(12), Constraint::Length(5), Constraint::Length(5), Constraint::Length(5), Constraint::Length(6)];
+        let table = Table::new(rows, widths)
+            .header(Row::new(vec!["Opponent", "W", "D", "L", "Score"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::default().borders(Borders::ALL).title("Against the opponents"));
+        frame.render_widget(table, area);
+    }
+
+    /// The function `draw_stats` renders the figures of the last iteration.
+    fn draw_stats(&self, frame: &mut Frame, area: Rect) {
+        let lines = match &self.latest {
+            None => vec![Line::raw("Waiting for the first iteration...")],
+            Some(latest) => {
+                let mut lines = vec![
+                    Line::raw(format!("W/D/L       {}/{}/{}", latest.wins, latest.draws, latest.losses)),
+                    Line::raw(format!("Loss        {:.4} -> {:.4}", latest.loss_before, latest.loss_after)),
+                    Line::raw(format!("Game length {:.1}", latest.mean_length)),
+                    Line::raw(format!("Games/s     {:.1}", latest.games_per_second)),
+                    Line::raw(format!("Iteration   {:.2}s", latest.seconds)),
+                ];
+                if let Some(stage) = latest.stage {
+                    lines.push(Line::raw(format!("Stage       {}", stage)));
+                }
+                if let (Some(rate), Some(blunders), Some(mse)) = (self.accuracy.last(), latest.blunders, latest.value_mse) {
+                    lines.push(Line::raw(format!("Optimal     {:.1}%", 100.0 * rate)));
+                    lines.push(Line::raw(format!("Blunders    {}", blunders)));
+                    lines.push(Line::raw(format!("Value MSE   {:.4}", mse)));
+                }
+                lines
+            }
+        };
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Last iteration")), area);
+    }
+
+    /// The function `draw_game` renders the final position of the last game.
+    fn draw_game(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("Last game");
+        let Some(game) = &self.last_game else {
+            frame.render_widget(Paragraph::new("No game yet").block(block), area);
+            return;
+        };
+        let cells: Vec<char> = game.final_position.chars().collect();
+        let mut lines: Vec<Line> = cells
+            .chunks(3)
+            .map(|row| {
+                let spans: Vec<Span> = row
+                    .iter()
+                    .map(|cell| match cell {
+                        'X' => Span::styled(" X ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+                        'O' => Span::styled(" O ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+                        _ => Span::raw(" . "),
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+        let result = match game.result {
+            1 => "won",
+            0 => "drawn",
+            _ => "lost",
+        };
+        lines.push(Line::raw(""));
+        lines.push(Line::raw(format!("vs {}", game.opponent)));
+        lines.push(Line::raw(format!("{} in {} moves", result, game.length)));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+/// The function `draw_sparkline` renders a history of values as a sparkline, scaled to its maximum.
+fn draw_sparkline(frame: &mut Frame, area: Rect, title: &str, values: &[f64], color: Color) {
+    let width = area.width.saturating_sub(2) as usize;
+    let shown = &values[values.len().saturating_sub(width)..];
+    let data: Vec<u64> = shown.iter().map(|value| (value.max(0.0) * 1000.0) as u64).collect();
+    let sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(title.to_string()))
+        .data(&data)
+        .style(Style::default().fg(color));
+    frame.render_widget(sparkline, area);
+}
@@ -0,0 +1,98 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+
+/// The enum `Error` lists the failures Acordion can recover from or report, each carrying the file
+/// it happened on, if any.
+///
+/// Variants:
+///
+/// * `Io`: An operating system error while reading, writing or listing a file or directory.
+/// * `Model`: A model file that exists but could not be encoded or decoded.
+/// * `Config`: A configuration file that is not valid TOML or has unknown keys.
+/// * `State`: A training state file that could not be written or parsed.
+/// * `Pool`: An opponent pool metadata file that could not be written or parsed.
+/// * `Ratings`: A ratings file that could not be written or parsed.
+/// * `Terminal`: The terminal could not be set up or drawn on by the training dashboard or the game.
+/// * `Book`: An opening book file with a line that is not a position followed by moves.
+/// * `Tablebase`: A file that is not an endgame tablebase written by Acordion.
+/// * `Metrics`: A line of the metrics log that could not be encoded as JSON.
+/// * `Curriculum`: A curriculum with a stage the trainee can never be promoted from.
+/// * `Panic`: Training panicked on its own thread while the dashboard was drawing.
+#[derive(Debug)]
+pub(crate) enum Error {
+    Io { path: PathBuf, source: io::Error },
+    Model { path: PathBuf, message: String },
+    Config { path: PathBuf, source: toml::de::Error },
+    State { path: PathBuf, message: String },
+    Pool { path: PathBuf, message: String },
+    Ratings { path: PathBuf, message: String },
+    Terminal { source: io::Error },
+    Book { path: PathBuf, message: String },
+    Tablebase { path: PathBuf, message: String },
+    Metrics { path: PathBuf, message: String },
+    Curriculum { message: String },
+    Panic { message: String },
+}
+
+/// The result type used throughout Acordion.
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The function `io` wraps an I/O error raised on `path`.
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        Error::Io { path: path.to_path_buf(), source }
+    }
+
+    /// The function `is_transient` tells whether the error may go away if the operation is retried: an
+    /// I/O error that was interrupted, timed out or met a busy resource.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Io { source, .. }
+                if matches!(source.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::ResourceBusy)
+        )
+    }
+
+    /// The function `terminal` wraps an I/O error raised by the terminal.
+    pub fn terminal(source: io::Error) -> Self {
+        Error::Terminal { source }
+    }
+
+    /// The function `model` converts an error of `neuroflow::io` raised on `path`, keeping I/O errors
+    /// apart from encoding errors.
+    pub fn model(path: &Path, source: neuroflow::ErrorKind) -> Self {
+        match source {
+            neuroflow::ErrorKind::IO(source) => Error::io(path, source),
+            other => Error::Model { path: path.to_path_buf(), message: format!("{:?}", other) },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Model { path, message } => write!(f, "{}: invalid model: {}", path.display(), message),
+            Error::Config { path, source } => write!(f, "{}: invalid configuration: {}", path.display(), source),
+            Error::State { path, message } => write!(f, "{}: invalid training state: {}", path.display(), message),
+            Error::Pool { path, message } => write!(f, "{}: invalid opponent pool: {}", path.display(), message),
+            Error::Ratings { path, message } => write!(f, "{}: invalid ratings: {}", path.display(), message),
+            Error::Terminal { source } => write!(f, "terminal: {}", source),
+            Error::Book { path, message } => write!(f, "{}: invalid opening book: {}", path.display(), message),
+            Error::Tablebase { path, message } => write!(f, "{}: invalid tablebase: {}", path.display(), message),
+            Error::Metrics { path, message } => write!(f, "{}: could not write metrics: {}", path.display(), message),
+            Error::Curriculum { message } => write!(f, "invalid curriculum: {}", message),
+            Error::Panic { message } => write!(f, "training panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Model { .. } | Error::State { .. } | Error::Pool { .. } | Error::Ratings { .. } | Error::Book { .. } | Error::Tablebase { .. } | Error::Metrics { .. } | Error::Curriculum { .. } | Error::Panic { .. } => None,
+            Error::Config { source, .. } => Some(source),
+            Error::Terminal { source } => Some(source),
+        }
+    }
+}
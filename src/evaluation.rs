@@ -0,0 +1,153 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{bitboard_functions, board::Board, minimax::Minimax, solver::Solver};
+
+/// The struct `Accuracy` is the report of an `Evaluator`.
+///
+/// Properties:
+///
+/// * `positions`: The `positions` property is the number of positions evaluated.
+/// * `optimal`: The `optimal` property is the number of positions where the agent chose a move that
+/// keeps the game-theoretic value of the position.
+/// * `drawn`: The `drawn` property is the number of positions whose value is a draw.
+/// * `blunders`: The `blunders` property is the number of drawn positions where the agent chose a
+/// losing move.
+/// * `mse`: The `mse` property is the mean squared error of the values the network gives to the legal
+/// moves, against their true values of 1, 0 or -1.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug)]
+pub(crate) struct Accuracy {
+    pub positions: usize,
+    pub optimal: usize,
+    pub drawn: usize,
+    pub blunders: usize,
+    pub mse: f64,
+}
+
+impl Accuracy {
+    /// The function `optimal_rate` returns the fraction of positions where the agent played optimally.
+    pub fn optimal_rate(&self) -> f64 {
+        self.optimal as f64 / self.positions.max(1) as f64
+    }
+}
+
+impl fmt::Display for Accuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "optimal moves {:.1}% ({}/{}), draw-to-loss blunders {}/{}, value MSE {:.4}",
+            100.0 * self.optimal_rate(),
+            self.optimal,
+            self.positions,
+            self.blunders,
+            self.drawn,
+            self.mse
+        )
+    }
+}
+
+/// The struct `Position` is a position of the evaluation set, solved once.
+///
+/// Properties:
+///
+/// * `board`: The `board` property is the position seen from the side to move.
+/// * `value`: The `value` property is the value of the position for the side to move.
+/// * `scores`: The `scores` property pairs each legal move with its value for the side to move.
+struct Position {
+    board: Board,
+    value: i8,
+    scores: Vec<(i16, i8)>,
+}
+
+/// The struct `Evaluator` measures an agent against the perfect solver on every position that can
+/// arise in a game and is not over, counting positions equal up to symmetry once.
+///
+/// Properties:
+///
+/// * `positions`: The `positions` property holds the evaluation set.
+pub(crate) struct Evaluator {
+    positions: Vec<Position>,
+}
+
+impl Evaluator {
+    /// The function `new` enumerates and solves the evaluation set.
+    pub fn new() -> Self {
+        let mut seen = HashSet::new();
+        let mut sides = Vec::new();
+        Self::collect(0, 0, &mut seen, &mut sides);
+
+        let mut solver = Solver::new();
+        let positions = sides
+            .into_iter()
+            .map(|(me, opp)| {
+                let board = Board::from_sides(me, opp);
+                Position {
+                    value: solver.solve(&board),
+                    scores: solver.score_moves(&board),
+                    board,
+                }
+            })
+            .collect();
+        Evaluator { positions }
+    }
+
+    /// The function `collect` walks the game tree from a position, adding the canonical form of every
+    /// position that is not over to `sides`.
+    fn collect(me: i16, opp: i16, seen: &mut HashSet<(i16, i16)>, sides: &mut Vec<(i16, i16)>) {
+        let (me, opp) = bitboard_functions::canonical(me, opp);
+        if !seen.insert((me, opp)) || Board::from_sides(me, opp).has_game_ended() {
+            return;
+        }
+        sides.push((me, opp));
+        for _move in bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD) {
+            Self::collect(opp, me | _move, seen, sides);
+        }
+    }
+
+    /// The function `evaluate` runs an agent over the evaluation set.
+    ///
+    /// Arguments:
+    ///
+    /// * `mm`: The `mm` parameter is the agent, playing the moves it would play in a game.
+    ///
+    /// Returns:
+    ///
+    /// The accuracy of the agent's moves and of its network's values.
+    pub fn evaluate(&self, mm: &mut Minimax) -> Accuracy {
+        let mut accuracy = Accuracy {
+            positions: self.positions.len(),
+            optimal: 0,
+            drawn: 0,
+            blunders: 0,
+            mse: 0.0,
+        };
+        let mut squared_error = 0.0;
+        let mut moves = 0;
+        for position in &self.positions {
+            let chosen = mm.minimax(position.board.clone());
+            let score = position
+                .scores
+                .iter()
+                .find(|(_move, _)| *_move == chosen)
+                .map(|(_, score)| *score)
+                .unwrap_or(-1);
+            if score == position.value {
+                accuracy.optimal += 1;
+            }
+            if position.value == 0 {
+                accuracy.drawn += 1;
+                if score < 0 {
+                    accuracy.blunders += 1;
+                }
+            }
+            for (_move, score) in &position.scores {
+                let mut next = position.board.clone();
+                next.play_move(*_move);
+                squared_error += (-mm.evaluate(&next) - *score as f64).powi(2);
+                moves += 1;
+            }
+        }
+        accuracy.mse = squared_error / moves.max(1) as f64;
+        accuracy
+    }
+}
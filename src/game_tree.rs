@@ -1,6 +1,9 @@
 pub(crate) struct GameTree {
     positions: Vec<Vec<f64>>,
     rewards: Vec<i8>,
+    result: i8,
+    length: u32,
+    final_position: String,
 }
 
 impl GameTree {
@@ -19,8 +22,9 @@ impl GameTree {
     /// 
     /// A new instance of the `GameTree` struct with the provided `positions` and `rewards` vectors is
     /// being returned.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn new(positions: Vec<Vec<f64>>, rewards: Vec<i8>) -> Self {
-        GameTree { positions, rewards }
+        GameTree { positions, rewards, result: 0, length: 0, final_position: String::new() }
     }
 
     /// The function `add_position` adds a new position to a mutable vector of positions.
@@ -29,6 +33,7 @@ impl GameTree {
     /// 
     /// * `new_position`: A vector of floating-point numbers representing the new position to be added
     /// to the existing positions.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn add_position(&mut self, new_position: Vec<f64>) {
         self.positions.push(new_position);
     }
@@ -39,6 +44,7 @@ impl GameTree {
     /// 
     /// * `new_reward`: The `new_reward` parameter is of type `i8`, which is a signed 8-bit integer in
     /// Rust.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn add_reward(&mut self, new_reward: i8) {
         self.rewards.push(new_reward);
     }
@@ -49,7 +55,7 @@ impl GameTree {
     /// 
     /// A vector of signed 8-bit integers (Vec<i8>) containing the rewards.
     pub fn get_rewards(&self) -> Vec<i8> {
-        return self.rewards.clone();
+        self.rewards.clone()
     }
 
     /// The function `get_positions` returns a clone of the positions stored in a vector of vectors of
@@ -59,6 +65,45 @@ impl GameTree {
     /// 
     /// A vector of vectors of type f64 containing positions is being returned.
     pub fn get_positions(&self) -> Vec<Vec<f64>> {
-        return self.positions.clone();
+        self.positions.clone()
     }
-}
\ No newline at end of file
+
+    /// The function `set_result` records how the game ended for the trainee.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `result`: The `result` parameter is `1` for a win, `0` for a draw and `-1` for a loss.
+    pub fn set_result(&mut self, result: i8) {
+        self.result = result;
+    }
+
+    /// The function `get_result` returns how the game ended for the trainee.
+    /// 
+    /// Returns:
+    /// 
+    /// `1` for a win, `0` for a draw and `-1` for a loss.
+    pub fn get_result(&self) -> i8 {
+        self.result
+    }
+
+    /// The function `set_length` records the number of moves the game lasted.
+    pub fn set_length(&mut self, length: u32) {
+        self.length = length;
+    }
+
+    /// The function `get_length` returns the number of moves the game lasted.
+    pub fn get_length(&self) -> u32 {
+        self.length
+    }
+
+    /// The function `set_final_position` records the position the game ended in, in the notation of
+    /// `Board::to_notation`.
+    pub fn set_final_position(&mut self, final_position: String) {
+        self.final_position = final_position;
+    }
+
+    /// The function `get_final_position` returns the position the game ended in.
+    pub fn get_final_position(&self) -> String {
+        self.final_position.clone()
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{bitboard_functions, board::Board};
+
+/// The center cell.
+const CENTER: i16 = 0b000010000;
+/// The four corners.
+const CORNERS: i16 = 0b101000101;
+/// The four cells in the middle of a side.
+const SIDES: i16 = 0b010101010;
+/// The index in `bitboard_functions::SYMMETRIES` of the half turn, which sends each corner to the
+/// opposite one.
+const HALF_TURN: usize = 2;
+
+/// The enum `Rule` lists the rules of the Newell & Simon strategy, in the order they are tried.
+///
+/// Variants:
+///
+/// * `Win`: Complete a line.
+/// * `Block`: Stop the opponent from completing a line.
+/// * `Fork`: Make two threats at once.
+/// * `BlockFork`: Stop the opponent from forking, by taking the fork square or by making a threat
+/// whose answer does not fork.
+/// * `Center`: Take the center.
+/// * `OppositeCorner`: Take the corner opposite one of the opponent's.
+/// * `EmptyCorner`: Take a corner.
+/// * `EmptySide`: Take the middle of a side.
+/// * `Random`: No rule was followed; the move was chosen at random.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Rule {
+    Win,
+    Block,
+    Fork,
+    BlockFork,
+    Center,
+    OppositeCorner,
+    EmptyCorner,
+    EmptySide,
+    Random,
+}
+
+impl Rule {
+    /// The rules of the strategy, in the order they are tried.
+    const ORDER: [Rule; 8] = [
+        Rule::Win,
+        Rule::Block,
+        Rule::Fork,
+        Rule::BlockFork,
+        Rule::Center,
+        Rule::OppositeCorner,
+        Rule::EmptyCorner,
+        Rule::EmptySide,
+    ];
+
+    /// The function `cells` returns the cells the rule would play in.
+    ///
+    /// Arguments:
+    ///
+    /// * `me`: The `me` parameter is the bitboard of the side to move.
+    /// * `opp`: The `opp` parameter is the bitboard of the other side.
+    ///
+    /// Returns:
+    ///
+    /// The bitboard of the cells, 0 if the rule does not apply.
+    fn cells(self, me: i16, opp: i16) -> i16 {
+        let empty = !(me | opp) & Board::BOARD;
+        match self {
+            Rule::Win => Board::line_completions(me, opp),
+            Rule::Block => Board::line_completions(opp, me),
+            Rule::Fork => Board::fork_squares(me, opp),
+            Rule::BlockFork => block_fork(me, opp),
+            Rule::Center => CENTER & empty,
+            Rule::OppositeCorner => bitboard_functions::transform(opp & CORNERS, HALF_TURN) & empty,
+            Rule::EmptyCorner => CORNERS & empty,
+            Rule::EmptySide => SIDES & empty,
+            Rule::Random => empty,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::Win => "win",
+            Rule::Block => "block",
+            Rule::Fork => "fork",
+            Rule::BlockFork => "block fork",
+            Rule::Center => "center",
+            Rule::OppositeCorner => "opposite corner",
+            Rule::EmptyCorner => "empty corner",
+            Rule::EmptySide => "empty side",
+            Rule::Random => "random",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The function `block_fork` returns the cells that keep the opponent from forking: its only fork
+/// square, or else the moves making a threat whose answer is not a fork, preferring those on a fork
+/// square, or else any fork square.
+fn block_fork(me: i16, opp: i16) -> i16 {
+    let forks = Board::fork_squares(opp, me);
+    if forks == 0 || forks.count_ones() == 1 {
+        return forks;
+    }
+    let forcing = bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD)
+        .into_iter()
+        .filter(|_move| {
+            let threats = Board::line_completions(me | _move, opp);
+            threats != 0 && threats & Board::fork_squares(opp, me | _move) == 0
+        })
+        .fold(0, |cells, _move| cells | _move);
+    if forcing & forks != 0 {
+        forcing & forks
+    } else if forcing != 0 {
+        forcing
+    } else {
+        forks
+    }
+}
+
+/// The struct `Heuristic` is a rule-based player following the strategy of Newell & Simon: win,
+/// block, fork, block a fork, center, opposite corner, empty corner, empty side. When a rule gives
+/// several cells it plays the first one, so that at full skill it always plays the same move.
+///
+/// Properties:
+///
+/// * `skill`: The `skill` property is the probability that each rule is followed, between 0 and 1;
+/// a rule that is not followed is skipped, and a move is chosen at random when every rule was.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct Heuristic {
+    skill: f64,
+}
+
+impl Heuristic {
+    /// The function `new` creates a player with the given skill, clamped between 0 and 1.
+    pub fn new(skill: f64) -> Self {
+        Heuristic { skill: skill.clamp(0.0, 1.0) }
+    }
+
+    /// The function `choose` picks the move of the player.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position to be played, which is not over.
+    /// * `rng`: The `rng` parameter decides which rules are skipped.
+    ///
+    /// Returns:
+    ///
+    /// The move and the rule it was chosen by.
+    pub fn choose(&self, board: &Board, rng: &mut impl Rng) -> (i16, Rule) {
+        let (me, opp) = board.get_sides();
+        for rule in Rule::ORDER {
+            if self.skill < 1.0 && !rng.gen_bool(self.skill) {
+                continue;
+            }
+            let cells = rule.cells(me, opp);
+            if cells != 0 {
+                return (cells & -cells, rule);
+            }
+        }
+        let moves = bitboard_functions::get_indi_bits(Rule::Random.cells(me, opp));
+        (*moves.choose(rng).expect("the game is not over"), Rule::Random)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::solver::Solver;
+
+    /// Plays the heuristic in `board` and every reply of the opponent after it, checking that no move
+    /// of the heuristic gives away the value of the position.
+    fn check_every_reply(board: &mut Board, heuristic: &Heuristic, solver: &mut Solver, rng: &mut ChaCha8Rng) {
+        if board.has_game_ended() {
+            return;
+        }
+        let value = solver.solve(board);
+        let (_move, rule) = heuristic.choose(board, rng);
+        board.play_move(_move);
+        assert!(-solver.solve(board) >= value, "{:?} gives away {}", rule, board.to_notation());
+        if !board.has_game_ended() {
+            for reply in bitboard_functions::get_indi_bits(board.clone().gen_move()) {
+                board.play_move(reply);
+                check_every_reply(board, heuristic, solver, rng);
+                board.undo_move(reply);
+            }
+        }
+        board.undo_move(_move);
+    }
+
+    #[test]
+    fn never_loses_at_full_skill() {
+        // Against every reply, which covers every game the perfect solver can play.
+        let heuristic = Heuristic::new(1.0);
+        let mut solver = Solver::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut board = Board::new();
+        check_every_reply(&mut board, &heuristic, &mut solver, &mut rng);
+        for first in bitboard_functions::get_indi_bits(Board::BOARD) {
+            board.play_move(first);
+            check_every_reply(&mut board, &heuristic, &mut solver, &mut rng);
+            board.undo_move(first);
+        }
+    }
+
+    #[test]
+    fn takes_a_win_before_blocking() {
+        let board = Board::from_notation("XX.OO....").unwrap();
+        assert_eq!(Heuristic::new(1.0).choose(&board, &mut ChaCha8Rng::seed_from_u64(0)), (1 << 2, Rule::Win));
+    }
+}
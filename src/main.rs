@@ -1,206 +1,547 @@
+use std::{process::ExitCode, sync::Arc, time::Duration};
+
 use board::Board;
-use minimax::Minimax;
+use clap::Parser;
+use book::{Booked, OpeningBook};
+use cli::{BookArgs, BookOptions, BookSource, Cli, Command, GauntletArgs, Side, TournamentArgs, TrainArgs};
+use config::TrainerConfig;
+use error::Result;
+use evaluation::Evaluator;
+use minimax::{Minimax, SearchLimits};
+use player::Player;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use ratings::Ratings;
+use solver::Solver;
+use tablebase::{Shape, Tablebase};
+use tournament::{AgentSpec, CrossTable, Record, Tournament};
 use trainer::Trainer;
+use workspace::Workspace;
 
 pub mod board;
 pub mod minimax;
 pub mod bitboard_functions;
 pub mod trainer;
 pub mod game_tree;
+pub mod workspace;
+pub mod config;
+pub mod cli;
+pub mod solver;
+pub mod error;
+pub mod state;
+pub mod pool;
+pub mod ratings;
+pub mod mcts;
+pub mod tournament;
+pub mod evaluation;
+pub mod metrics;
+pub mod dashboard;
+pub mod play;
+pub mod move_ordering;
+pub mod heuristic;
+pub mod player;
+pub mod curriculum;
+pub mod book;
+pub mod tablebase;
+pub mod qlearning;
 
 
-fn main() {
-    let mut train= Trainer::new(neuroflow::io::load("src/Trainee/acordion.flow").unwrap());
-    let _ = train.reset();
-    train.train(1, 10000, 2, 8);
-    play_game_with_user();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if !cli.dashboard() {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    }
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
 
-pub fn play_game_with_user() {
-    let mut board: Board = Board::new();
-    let mut mm: Minimax = Minimax::new();
-
-    let (mut turn, player_type) = get_user_preference();
+/// The function `run` opens the workspace and executes the requested subcommand.
+/// 
+/// Arguments:
+/// 
+/// * `cli`: The `cli` parameter holds the parsed command line.
+/// 
+/// Returns:
+/// 
+/// The exit code of the subcommand, or the error that interrupted it.
+fn run(cli: Cli) -> Result<ExitCode> {
+    let workspace = Workspace::open(&cli.workspace)?;
+    println!("Using workspace {}", workspace.root().display());
 
-    println!("Please enter the moves according to the table given below:");
-    for i in 1..10 {
-        print!("| {} ", 10 - i);
-        if i % 3 == 0 {
-            println!("|");
-            //println!("______________");
+    match cli.command {
+        Command::Train(args) => run_training(&workspace, &args),
+        Command::Play { side, plain: true, book, book_plies } => {
+            play_plain_game(&workspace, side == Side::X, &BookOptions { book, book_plies })
+        }
+        Command::Play { side, plain: false, .. } => play_with_user(&workspace, side == Side::X),
+        Command::Eval { position, depth, nodes, movetime, tablebase } => {
+            let limits = SearchLimits {
+                depth: depth.or(if nodes.is_none() && movetime.is_none() { Some(0) } else { None }),
+                nodes,
+                time: movetime.map(Duration::from_millis),
+            };
+            let tablebase = match tablebase {
+                Some(path) => Some(Arc::new(Tablebase::load_for_game(&path)?)),
+                None => workspace.load_tablebase()?,
+            };
+            evaluate_position(&workspace, &position, limits, tablebase)
+        }
+        Command::Selfplay(book) => play_game_with_itself(&workspace, &book).map(|_| ExitCode::SUCCESS),
+        Command::Solve { position } => Ok(solve_position(&position)),
+        Command::Accuracy => {
+            let accuracy = Evaluator::new().evaluate(&mut Minimax::new(workspace.load_trainee()?));
+            println!("Trainee: {}", accuracy);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Bench { depth } => run_bench(&workspace, depth),
+        Command::Ratings => {
+            print_leaderboard(&workspace.load_ratings()?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Gauntlet(args) => run_gauntlet(&workspace, &args),
+        Command::Tournament(args) => run_tournament(&workspace, args),
+        Command::Book(args) => build_book(&workspace, &args),
+        Command::Tablebase { rows, cols, k, empty, output } => {
+            let Some(shape) = Shape::new(rows, cols, k) else {
+                eprintln!("No line of {} stones fits on a {}x{} board", k, rows, cols);
+                return Ok(ExitCode::FAILURE);
+            };
+            let output = match output {
+                Some(output) => output,
+                None if shape == Shape::TIC_TAC_TOE => workspace.tablebase_path(),
+                None => {
+                    eprintln!("The agents only probe tables of the {}: give the file of a table of the {} with --output", Shape::TIC_TAC_TOE, shape);
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            let tablebase = Tablebase::generate(shape, empty);
+            tablebase.save(&output)?;
+            println!("Tablebase of {} positions of the {} with at most {} empty cells written to {}", tablebase.len(), tablebase.shape(), tablebase.max_empty(), output.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Export { output, json } => {
+            workspace.export_trainee(&output, json)?;
+            println!("Trainee exported to {}", output.display());
+            Ok(ExitCode::SUCCESS)
         }
     }
+}
 
-    for _i in 0..10 {
-        if !turn == player_type {
-            play_user_move(&mut board);
+/// The function `run_training` trains the trainee of the workspace with the configuration read from
+/// `--config` (or the `trainer.toml` of the workspace, if any) and the command line overrides. With
+/// `--resume` it instead continues the interrupted run of the workspace, with the configuration it was
+/// started with and the command line overrides. A new run is refused while the workspace holds an
+/// interrupted one, unless `--force` is given. With `--dashboard` the run is shown live in the terminal.
+/// 
+/// Arguments:
+/// 
+/// * `workspace`: The `workspace` parameter is the run directory being trained in.
+/// * `args`: The `args` parameter holds the options given to the `train` subcommand.
+fn run_training(workspace: &Workspace, args: &TrainArgs) -> Result<ExitCode> {
+    if args.resume {
+        let state = workspace.load_state()?;
+        let config = args.apply(state.config.clone());
+        config.validate()?;
+        let mut train= Trainer::new(workspace.load_checkpoint(&state)?, workspace.clone(), config);
+        if args.dashboard {
+            dashboard::run(train, move |train| train.resume(state))?;
         } else {
-            play_computer_move(&mut mm, &mut board);
+            train.resume(state)?;
         }
+        return Ok(ExitCode::SUCCESS);
+    }
+    if !args.force && workspace.state_path().is_file() {
+        eprintln!(
+            "{} holds an interrupted run: continue it with `train --resume`, or discard it with `train --force`",
+            workspace.root().display()
+        );
+        return Ok(ExitCode::FAILURE);
+    }
 
-        if player_type {
-            print_board_1(&board);
-        } else {
-            print_board_2(&board);
-        }
+    let config_path = args.config.clone().unwrap_or_else(|| workspace.config_path());
+    let config = if args.config.is_some() || config_path.is_file() {
+        TrainerConfig::load(&config_path)?
+    } else {
+        TrainerConfig::default()
+    };
+    let config = args.apply(config);
+    config.validate()?;
 
-        if board.has_game_ended() {
+    let mut train= Trainer::new(workspace.load_trainee()?, workspace.clone(), config);
+    train.reset()?;
+    if args.dashboard {
+        dashboard::run(train, |train| train.train())?;
+    } else {
+        train.train()?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-            if player_type {
-                print_win_1(turn, &board);
-            } else {
-                print_win_2(turn, &board)
-            }
+/// The function `run_gauntlet` plays an agent against the random and the perfect player with both
+/// colors and checks the results against the thresholds of the command line.
+/// 
+/// Arguments:
+/// 
+/// * `workspace`: The `workspace` parameter is the run directory the networks are loaded from.
+/// * `args`: The `args` parameter holds the options given to the `gauntlet` subcommand.
+/// 
+/// Returns:
+/// 
+/// `ExitCode::SUCCESS` if both thresholds are met and `ExitCode::FAILURE` otherwise.
+fn run_gauntlet(workspace: &Workspace, args: &GauntletArgs) -> Result<ExitCode> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    println!("Gauntlet of {}, {} games per opponent and color, seed {}", args.agent, args.games, seed);
+    println!("{:<10} {:<5} {:>5} {:>5} {:>5} {:>7}", "Opponent", "Side", "W", "D", "L", "Score");
 
-            break;
+    let mut totals = Vec::new();
+    for opponent in [AgentSpec::Random, AgentSpec::Solver] {
+        let mut total = Record::default();
+        for (as_x, side) in [(true, "X"), (false, "O")] {
+            let record = tournament::play_series(workspace, &args.agent, &opponent, args.games, as_x, seed)?;
+            println!("{:<10} {:<5} {:>5} {:>5} {:>5} {:>6.1}%", opponent.to_string(), side, record.wins, record.draws, record.losses, 100.0 * record.score());
+            total.merge(&record);
         }
-        
-        turn = !turn;
-        println!("----------------------------------------------------------------------------------------------------------------");
+        totals.push(total);
     }
+
+    let random_wins = totals[0].wins as f64 / totals[0].games().max(1) as f64;
+    let random_passed = random_wins >= args.min_random_wins;
+    let solver_passed = totals[1].losses <= args.max_solver_losses;
+    println!(
+        "Against random: won {:.1}% (at least {:.1}% required) {}",
+        100.0 * random_wins,
+        100.0 * args.min_random_wins,
+        if random_passed { "PASS" } else { "FAIL" }
+    );
+    println!(
+        "Against solver: lost {} (at most {} allowed) {}",
+        totals[1].losses,
+        args.max_solver_losses,
+        if solver_passed { "PASS" } else { "FAIL" }
+    );
+    Ok(if random_passed && solver_passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
 
-/// The function `get_user_preference` in Rust prompts the user to choose between 'X' and 'O' and
-/// returns a tuple indicating the player's turn and type based on the choice.
-fn get_user_preference() -> (bool, bool) {
-    let mut turn = true;
-    let mut player_type = false;
-    
-    println!("Do you want to take 'X' or 'O'?");
+/// The function `run_tournament` plays a round robin between the given agents, prints the cross table
+/// and, unless `--unrated` is given, adds the games to the ratings of the workspace. The agents must
+/// have different names, which key both the cross table and the ratings.
+/// 
+/// Arguments:
+/// 
+/// * `workspace`: The `workspace` parameter is the run directory the networks are loaded from.
+/// * `args`: The `args` parameter holds the options given to the `tournament` subcommand.
+fn run_tournament(workspace: &Workspace, args: TournamentArgs) -> Result<ExitCode> {
+    let names: Vec<String> = args.agents.iter().map(|agent| agent.to_string()).collect();
+    if let Some(name) = names.iter().enumerate().find_map(|(i, name)| names[..i].contains(name).then_some(name)) {
+        eprintln!("Two entrants are named '{}', so their games would be counted as one player's: give each agent once and network files different names", name);
+        return Ok(ExitCode::FAILURE);
+    }
+    let book = match &args.book.book {
+        Some(path) => Some((Arc::new(OpeningBook::load(path)?), args.book.book_plies)),
+        None => None,
+    };
+    let tournament = Tournament {
+        agents: args.agents,
+        games: args.games,
+        random_plies: args.random_plies,
+        seed: args.seed.unwrap_or_else(rand::random),
+        book,
+    };
+    println!("Seed {}", tournament.seed);
+    let table = tournament.run(workspace)?;
+    print_cross_table(&table);
 
-    let mut line: String = String::new();
+    if !args.unrated {
+        let mut ratings = workspace.load_ratings()?;
+        table.rate(&mut ratings);
+        workspace.save_ratings(&ratings)?;
+        println!();
+        print_leaderboard(&ratings);
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-    let _ = std::io::stdin().read_line(&mut line).unwrap();
+/// The function `print_cross_table` prints the wins, draws and losses of every pairing of a tournament,
+/// followed by the total score of each entrant with its 95% confidence interval.
+fn print_cross_table(table: &CrossTable) {
+    let width = table.names.iter().map(|name| name.len()).max().unwrap_or(0).max(10);
+    print!("{:<width$}", "", width = width);
+    for name in &table.names {
+        print!(" {:>width$}", name, width = width);
+    }
+    println!(" {:>7} {:>8}", "Score", "95% CI");
+    for (i, name) in table.names.iter().enumerate() {
+        print!("{:<width$}", name, width = width);
+        for (j, record) in table.records[i].iter().enumerate() {
+            let cell = if i == j {
+                "-".to_string()
+            } else {
+                format!("{}-{}-{}", record.wins, record.draws, record.losses)
+            };
+            print!(" {:>width$}", cell, width = width);
+        }
+        let total = table.total(i);
+        println!(" {:>6.1}% {:>7}", 100.0 * total.score(), format!("±{:.1}%", 100.0 * total.confidence()));
+    }
+    println!("Cells are wins-draws-losses of the row against the column.");
+}
 
-    if line.starts_with("X") {
-        turn = false;
-        player_type = true;
+/// The function `parse_position` parses a position given on the command line, reporting malformed
+/// input to the user.
+fn parse_position(position: &str) -> Option<Board> {
+    let board = Board::from_notation(position);
+    if board.is_none() {
+        eprintln!("'{}' is not a valid position: expected 9 cells of X, O or . with X moving first", position);
     }
-    (turn, player_type)
+    board
 }
 
-/// The function `print_win_1` prints out the result of a game based on the current turn and the state
-/// of the game board.
+/// The function `evaluate_position` prints the score the trainee gives to every legal move of a
+/// position, the move it would play and the line it expects.
 /// 
 /// Arguments:
 /// 
-/// * `turn`: The `turn` parameter represents whose turn it is in the game. It is a boolean value where
-/// `true` typically represents the player's turn and `false` represents the AI's turn.
-/// * `board`: The `board` parameter is a reference to an instance of the `Board` struct. It is used to
-/// represent the current state of the game board, which likely includes information about the positions
-/// of the players' pieces and the game's progress.
-fn print_win_1(turn: bool, board: &Board) {
-    if !turn && (board.is_a_win()) {
-        println!("You won.");
-    } else if turn && (board.is_a_win()) {
-        println!("AI won.");
-    } else {
-        println!("It is a draw.");
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from.
+/// * `position`: The `position` parameter is the position in the notation of `Board::from_notation`.
+/// * `limits`: The `limits` parameter bounds the search.
+/// * `tablebase`: The `tablebase` parameter scores the leaves of the search it covers, if given.
+fn evaluate_position(workspace: &Workspace, position: &str, limits: SearchLimits, tablebase: Option<Arc<Tablebase>>) -> Result<ExitCode> {
+    let Some(mut board) = parse_position(position) else {
+        return Ok(ExitCode::FAILURE);
+    };
+    if board.has_game_ended() {
+        println!("The game is already over.");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut mm: Minimax = Minimax::new(workspace.load_trainee()?);
+    mm.set_tablebase(tablebase);
+    let result = mm.search(&board, limits);
+    for (_move, score) in &result.moves {
+        println!("Cell {}: {:+.4}", bitboard_functions::to_cell(*_move), score);
     }
+    let line: Vec<String> = result.principal_variation.iter().map(|_move| bitboard_functions::to_cell(*_move).to_string()).collect();
+    println!("The trainee plays cell {} ({:+.4})", bitboard_functions::to_cell(result.best_move), result.score);
+    println!("Principal variation: {}", line.join(" "));
+    println!(
+        "Searched {} positions to depth {} in {:.2?}{}",
+        result.nodes,
+        result.depth,
+        result.elapsed,
+        if result.stopped { ", stopped by a limit" } else { "" }
+    );
+    Ok(ExitCode::SUCCESS)
 }
 
-/// The function `print_win_2` prints a message based on the game outcome (win/lose/draw) for either the
-/// AI or the player.
+/// The positions searched by `bench`: the empty board, the three openings up to symmetry and three
+/// positions from the middle of a game.
+const BENCH_POSITIONS: [&str; 7] = [".........", "X........", ".X.......", "....X....", "X...O....", "X.O.X....", ".X..O..X."];
+
+/// The function `run_bench` searches the `BENCH_POSITIONS` with the trainee, first with the moves in
+/// cell order and then with move ordering, and prints how many positions each search visited.
 /// 
 /// Arguments:
 /// 
-/// * `turn`: The `turn` parameter is a boolean value that indicates whose turn it is in the game.
-/// `true` typically represents the player's turn, while `false` represents the AI's turn.
-/// * `board`: The `board` parameter is a reference to an instance of the `Board` struct.
-fn print_win_2(turn: bool, board: &Board) {
-    if !turn && (board.is_a_win()) {
-        println!("AI won.");
-    } else if turn && (board.is_a_win()) {
-        println!("You won.");
-    } else {
-        println!("It is a draw.");
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from.
+/// * `depth`: The `depth` parameter is the deepest search, if any.
+fn run_bench(workspace: &Workspace, depth: Option<u8>) -> Result<ExitCode> {
+    let mut mm = Minimax::new(workspace.load_trainee()?);
+    let limits = SearchLimits { depth, ..SearchLimits::default() };
+    let mut totals = [0, 0];
+    println!("{:<11} {:>12} {:>12} {:>8}", "position", "unordered", "ordered", "saved");
+    for position in BENCH_POSITIONS {
+        let board = Board::from_notation(position).expect("bench positions are valid");
+        let mut nodes = [0, 0];
+        for (i, ordering) in [false, true].into_iter().enumerate() {
+            mm.set_move_ordering(ordering);
+            nodes[i] = mm.search(&board, limits).nodes;
+            totals[i] += nodes[i];
+        }
+        println!("{:<11} {:>12} {:>12} {:>7.1}%", position, nodes[0], nodes[1], 100.0 * (1.0 - nodes[1] as f64 / nodes[0] as f64));
     }
+    println!("{:<11} {:>12} {:>12} {:>7.1}%", "total", totals[0], totals[1], 100.0 * (1.0 - totals[1] as f64 / totals[0] as f64));
+    Ok(ExitCode::SUCCESS)
 }
 
-/// The function `play_computer_move` uses the minimax algorithm to determine the best move for the
-/// computer player and then plays that move on the board.
+/// The function `solve_position` prints the game-theoretic value of a position and of each of its
+/// moves.
 /// 
 /// Arguments:
 /// 
-/// * `mm`: Minimax algorithm implementation used for determining the best move in a game.
-/// * `board`: The `board` parameter represents the game board on which the minimax algorithm will make
-/// its move. It is a mutable reference to the game board, allowing the algorithm to make changes to the
-/// board during the minimax calculation and when playing the move.
-fn play_computer_move(mm: &mut Minimax, board: &mut Board) {
-    let mut _move: i16 = mm.minimax(board.clone());
-    board.play_move(_move)
+/// * `position`: The `position` parameter is the position in the notation of `Board::from_notation`.
+fn solve_position(position: &str) -> ExitCode {
+    let Some(board) = parse_position(position) else {
+        return ExitCode::FAILURE;
+    };
+    let mut solver = Solver::new();
+    println!("{} for the side to move", verdict(solver.solve(&board)));
+    for (_move, score) in solver.score_moves(&board) {
+        println!("Cell {}: {}", bitboard_functions::to_cell(_move), verdict(score));
+    }
+    let best: Vec<String> = solver.best_moves(&board).into_iter().map(|_move| bitboard_functions::to_cell(_move).to_string()).collect();
+    if !best.is_empty() {
+        println!("Best cells: {}", best.join(", "));
+    }
+    ExitCode::SUCCESS
+}
+
+/// The function `verdict` names a value returned by the solver.
+fn verdict(score: i8) -> &'static str {
+    match score {
+        1 => "Win",
+        0 => "Draw",
+        _ => "Loss",
+    }
 }
 
-/// The function `play_user_move` reads a user input for a move, converts it to an integer, and then
-/// plays that move on the board.
+/// The function `play_game_with_itself` lets the trainee play both sides of a game, printing the board
+/// after every move.
 /// 
 /// Arguments:
 /// 
-/// * `board`: The `board` parameter is a mutable reference to a `Board` object.
-fn play_user_move(board: &mut Board) {
-    let mut _move: String = String::new();
-    println!("Enter the move: ");
-    let _ = std::io::stdin().read_line(&mut _move).unwrap();
-    let _move: i8 = _move.trim().parse().expect("REASON");
-    board.play_move(1<<(9 - _move));
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from.
+/// * `book`: The `book` parameter names the opening book both sides play from, if any.
+fn play_game_with_itself(workspace: &Workspace, book: &BookOptions) -> Result<()> {
+    let mut x = trainee_player(workspace, book)?;
+    let mut o = trainee_player(workspace, book)?;
+    let record = player::play_match(&mut x, &mut o, &[], None, &mut ChaCha8Rng::from_entropy());
+
+    let mut board: Board = Board::new();
+    for (ply, _move) in record.moves.iter().enumerate() {
+        if ply > 0 {
+            println!("----------------------------------------------------------------------------------------------------------------");
+        }
+        board.play_move(*_move);
+        print_board(&board);
+    }
+    println!("Final position: {}", board.to_notation());
+    match record.result {
+        1 => println!("X won."),
+        -1 => println!("O won."),
+        _ => println!("It is a draw."),
+    }
+    Ok(())
 }
 
-/// The function `print_board_1` prints a Tic-Tac-Toe board represented by a vector of floats.
+/// The function `trainee_player` loads the trainee as a player, playing from an opening book if one
+/// is given.
+///
+/// Arguments:
+///
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from.
+/// * `book`: The `book` parameter names the opening book, if any.
+fn trainee_player(workspace: &Workspace, book: &BookOptions) -> Result<Box<dyn Player>> {
+    let trainee = workspace.trainee_search()?;
+    Ok(match &book.book {
+        Some(path) => Box::new(Booked { player: trainee, book: Arc::new(OpeningBook::load(path)?), plies: book.book_plies }),
+        None => Box::new(trainee),
+    })
+}
+
+/// The function `build_book` builds an opening book and writes it to a file.
+///
+/// Arguments:
+///
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from and the
+/// book written to by default.
+/// * `args`: The `args` parameter holds the options given to the `book` subcommand.
+#[allow(clippy::doc_lazy_continuation)]
+fn build_book(workspace: &Workspace, args: &BookArgs) -> Result<ExitCode> {
+    let book = match args.source {
+        BookSource::Solver => OpeningBook::from_solver(args.plies),
+        BookSource::Selfplay => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            println!("Playing {} self-play games, seed {}", args.games, seed);
+            let x = Box::new(workspace.trainee_search()?);
+            let o = Box::new(workspace.trainee_search()?);
+            let games = book::self_play(x, o, args.games, args.exploration, &mut ChaCha8Rng::seed_from_u64(seed));
+            OpeningBook::from_games(&games, args.plies)
+        }
+    };
+    let output = args.output.clone().unwrap_or_else(|| workspace.book_path());
+    book.save(&output)?;
+    println!("Opening book of {} positions written to {}", book.len(), output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The function `play_plain_game` plays one game between the user, on the standard input, and the
+/// trainee, then rates it.
+///
+/// Arguments:
+///
+/// * `workspace`: The `workspace` parameter is the run directory the trainee and the ratings belong to.
+/// * `human_x`: The `human_x` parameter tells whether the user plays X.
+/// * `book`: The `book` parameter names the opening book the trainee plays from, if any.
+fn play_plain_game(workspace: &Workspace, human_x: bool, book: &BookOptions) -> Result<ExitCode> {
+    let mut trainee = trainee_player(workspace, book)?;
+    let mut human = player::Human;
+    let mut rng = ChaCha8Rng::from_entropy();
+    let record = if human_x {
+        player::play_match(&mut human, &mut trainee, &[], None, &mut rng)
+    } else {
+        player::play_match(&mut trainee, &mut human, &[], None, &mut rng)
+    };
+
+    let mut board = Board::new();
+    record.moves.iter().for_each(|_move| board.play_move(*_move));
+    print_board(&board);
+    let score = if human_x { record.x_score() } else { 1.0 - record.x_score() };
+    let mut ratings = workspace.load_ratings()?;
+    ratings.record(ratings::HUMAN, ratings::TRAINEE, score);
+    workspace.save_ratings(&ratings)?;
+    println!("Ratings: {} {:.0}, {} {:.0}", ratings::HUMAN, ratings.get(ratings::HUMAN).elo, ratings::TRAINEE, ratings.get(ratings::TRAINEE).elo);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The function `play_with_user` runs the terminal game against the trainee and prints the results of
+/// the session once the user quits.
 /// 
 /// Arguments:
 /// 
-/// * `board`: The function `print_board_1` is to print a Tic-Tac-Toe board
-/// represented by a vector of floats. The board is expected to be a 3x3 grid where each cell can
-/// contain a value of 1.0 (for 'X'), -1
-fn print_board_1(board: &Board) {
-    let x: Vec<f64> = board.get_board_in_array();
-    for i in 0..x.len(){
-        print!("| ");
-        let _type = if x[i] == 1.0 {
-            "X"
-        } else if x[i] == -1.0 {
-            "O"
-        } else {
-            " "
-        };
-        print!("{}", _type);
-        print!(" ");
-        if (i + 1) % 3 == 0 {
-            println!(" |");
-        }
-    
-        if (i + 1) % 3 == 0 {
-            println!();
-        }
+/// * `workspace`: The `workspace` parameter is the run directory the trainee and the ratings belong to.
+/// * `human_x`: The `human_x` parameter tells whether the user plays X in the first game.
+fn play_with_user(workspace: &Workspace, human_x: bool) -> Result<ExitCode> {
+    let session = play::run(workspace, human_x)?;
+    println!("Session: won {}, drawn {}, lost {}", session.wins, session.draws, session.losses);
+    let ratings = workspace.load_ratings()?;
+    println!("Ratings: {} {:.0}, {} {:.0}", ratings::HUMAN, ratings.get(ratings::HUMAN).elo, ratings::TRAINEE, ratings.get(ratings::TRAINEE).elo);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The function `print_leaderboard` prints the rated players from the strongest to the weakest.
+fn print_leaderboard(ratings: &Ratings) {
+    let board = ratings.leaderboard();
+    if board.is_empty() {
+        println!("No rated games yet.");
+        return;
+    }
+    println!("{:<4} {:<20} {:>6} {:>6} {:>5} {:>5} {:>5}", "#", "Player", "Elo", "Games", "W", "D", "L");
+    for (rank, (name, rating)) in board.iter().enumerate() {
+        println!("{:<4} {:<20} {:>6.0} {:>6} {:>5} {:>5} {:>5}", rank + 1, name, rating.elo, rating.games(), rating.wins, rating.draws, rating.losses);
     }
 }
 
-/// The function `print_board_2` prints a Tic-Tac-Toe board represented by a vector of floats.
+/// The function `print_board` prints a position as a grid of X, O and empty cells.
 /// 
 /// Arguments:
 /// 
-/// * `board`: The function `print_board_2` is to print a Tic-Tac-Toe board
-/// represented by a vector of `f64` values. The values -1.0, 1.0, and 0.0 represent 'X', 'O', and empty
-/// space
-fn print_board_2(board: &Board) {
-    let x: Vec<f64> = board.get_board_in_array();
-    for i in 0..x.len(){
-        print!("| ");
-        let _type = if x[i] == -1.0 {
-            "X"
-        } else if x[i] == 1.0 {
-            "O"
-        } else {
-            " "
-        };
-        print!("{}", _type);
-        print!(" ");
-        if (i + 1) % 3 == 0 {
-            println!(" |");
-        }
-    
-        if (i + 1) % 3 == 0 {
-            println!();
+/// * `board`: The `board` parameter is the position to print, seen from either side.
+fn print_board(board: &Board) {
+    let notation = board.to_notation();
+    for row in notation.as_bytes().chunks(3) {
+        for cell in row {
+            let _type = if *cell == b'.' { ' ' } else { *cell as char };
+            print!("| {} ", _type);
         }
+        println!(" |");
+        println!();
     }
 }
@@ -0,0 +1,167 @@
+use rand::Rng;
+
+use crate::{bitboard_functions, board::Board};
+
+/// The struct `Node` is a position of the Monte Carlo search tree.
+///
+/// Properties:
+///
+/// * `me`: The `me` property is the bitboard of the player to move in this position.
+/// * `opp`: The `opp` property is the bitboard of the player who just moved.
+/// * `_move`: The `_move` property is the move that led from the parent to this position.
+/// * `parent`: The `parent` property is the index of the parent node, `None` for the root.
+/// * `children`: The `children` property holds the indices of the expanded children.
+/// * `untried`: The `untried` property is the bitboard of the moves not expanded yet.
+/// * `visits`: The `visits` property is the number of simulations that went through this node.
+/// * `wins`: The `wins` property is the total score of those simulations for the player who just
+/// moved, counting a draw as half a win.
+#[allow(clippy::doc_lazy_continuation)]
+struct Node {
+    me: i16,
+    opp: i16,
+    _move: i16,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: i16,
+    visits: u32,
+    wins: f64,
+}
+
+/// The struct `Mcts` is a Monte Carlo tree search player using UCT selection and uniformly random
+/// playouts.
+///
+/// Properties:
+///
+/// * `simulations`: The `simulations` property is the number of playouts run per move.
+pub(crate) struct Mcts {
+    simulations: u32,
+}
+
+impl Mcts {
+    /// The exploration constant of UCT.
+    const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+    /// The function `new` creates a searcher running `simulations` playouts per move.
+    pub fn new(simulations: u32) -> Self {
+        Mcts {
+            simulations: simulations.max(1),
+        }
+    }
+
+    /// The function `search` chooses a move for the side to move.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position to be played; the game must not be over.
+    /// * `rng`: The `rng` parameter drives the playouts.
+    ///
+    /// Returns:
+    ///
+    /// The most visited move of the root.
+    pub fn search(&self, board: &Board, rng: &mut impl Rng) -> i16 {
+        let (me, opp) = board.get_sides();
+        let mut tree = vec![Node {
+            me,
+            opp,
+            _move: 0,
+            parent: None,
+            children: Vec::new(),
+            untried: Self::legal_moves(me, opp),
+            visits: 0,
+            wins: 0.0,
+        }];
+
+        for _ in 0..self.simulations {
+            // Selection
+            let mut node = 0;
+            while tree[node].untried == 0 && !tree[node].children.is_empty() {
+                node = self.select_child(&tree, node);
+            }
+            // Expansion
+            if tree[node].untried != 0 {
+                let moves = bitboard_functions::get_indi_bits(tree[node].untried);
+                let _move = moves[rng.gen_range(0..moves.len())];
+                tree[node].untried &= !_move;
+                let (me, opp) = (tree[node].opp, tree[node].me | _move);
+                tree.push(Node {
+                    me,
+                    opp,
+                    _move,
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: Self::legal_moves(me, opp),
+                    visits: 0,
+                    wins: 0.0,
+                });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+            // Simulation, scored for the player who just moved into `node`.
+            let mut score = Self::playout(tree[node].me, tree[node].opp, rng);
+            // Backpropagation
+            let mut current = Some(node);
+            while let Some(index) = current {
+                tree[index].visits += 1;
+                tree[index].wins += score;
+                score = 1.0 - score;
+                current = tree[index].parent;
+            }
+        }
+
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|child| tree[**child].visits)
+            .map(|child| tree[*child]._move)
+            .unwrap_or_else(|| bitboard_functions::get_lsb(Self::legal_moves(me, opp) as i32) as i16)
+    }
+
+    /// The function `select_child` returns the child of `node` with the highest UCT value.
+    fn select_child(&self, tree: &[Node], node: usize) -> usize {
+        let parent_visits = (tree[node].visits.max(1) as f64).ln();
+        *tree[node]
+            .children
+            .iter()
+            .max_by(|a, b| Self::uct(&tree[**a], parent_visits).total_cmp(&Self::uct(&tree[**b], parent_visits)))
+            .expect("selected nodes have children")
+    }
+
+    /// The function `uct` scores a child for selection.
+    fn uct(child: &Node, parent_visits: f64) -> f64 {
+        let visits = child.visits.max(1) as f64;
+        child.wins / visits + Self::EXPLORATION * (parent_visits / visits).sqrt()
+    }
+
+    /// The function `legal_moves` returns the empty cells, or no move once the game is over.
+    fn legal_moves(me: i16, opp: i16) -> i16 {
+        if Board::has_line(opp) || Board::has_line(me) {
+            0
+        } else {
+            !(me | opp) & Board::BOARD
+        }
+    }
+
+    /// The function `playout` finishes the game with random moves.
+    ///
+    /// Returns:
+    ///
+    /// The score of the player who made the last move before the playout: 1, 0.5 or 0.
+    fn playout(mut me: i16, mut opp: i16, rng: &mut impl Rng) -> f64 {
+        // `opp` just moved; `mover_is_last` tracks whether that player made the latest move.
+        let mut mover_is_last = true;
+        loop {
+            if Board::has_line(opp) {
+                return if mover_is_last { 1.0 } else { 0.0 };
+            }
+            let empty = !(me | opp) & Board::BOARD;
+            if empty == 0 {
+                return 0.5;
+            }
+            let moves = bitboard_functions::get_indi_bits(empty);
+            let _move = moves[rng.gen_range(0..moves.len())];
+            (me, opp) = (opp, me | _move);
+            mover_is_last = !mover_is_last;
+        }
+    }
+}
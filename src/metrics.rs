@@ -0,0 +1,204 @@
+use std::{fs::{self, File, OpenOptions}, io::Write, path::{Path, PathBuf}};
+
+use serde::Serialize;
+
+use crate::{curriculum::Stage, error::{Error, Result}, evaluation::Accuracy, trainer::Opponent};
+
+/// The struct `GameMetrics` describes a training game.
+///
+/// Properties:
+///
+/// * `iteration`: The `iteration` property is the iteration the game was played in.
+/// * `opponent`: The `opponent` property is the opponent the trainee played, e.g. `snapshot-3`.
+/// * `length`: The `length` property is the number of moves of the game.
+/// * `result`: The `result` property is the trainee's result: 1, 0 or -1.
+/// * `final_position`: The `final_position` property is the position the game ended in, in the
+/// notation of `Board::to_notation`.
+/// * `loss_before`: The `loss_before` property is the mean squared error of the trainee on the
+/// positions of the game before it was fitted on them.
+/// * `loss_after`: The `loss_after` property is the same error after fitting.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GameMetrics {
+    pub iteration: i16,
+    pub opponent: Opponent,
+    pub length: u32,
+    pub result: i8,
+    pub final_position: String,
+    pub loss_before: f64,
+    pub loss_after: f64,
+}
+
+/// The struct `IterationMetrics` summarizes a training iteration.
+///
+/// Properties:
+///
+/// * `iteration`: The `iteration` property is the number of the iteration.
+/// * `games`: The `games` property is the number of games played.
+/// * `wins`: The `wins` property is the number of games the trainee won.
+/// * `draws`: The `draws` property is the number of games drawn.
+/// * `losses`: The `losses` property is the number of games the trainee lost.
+/// * `mean_length`: The `mean_length` property is the average number of moves per game.
+/// * `loss_before`: The `loss_before` property is the average of the games' `loss_before`.
+/// * `loss_after`: The `loss_after` property is the average of the games' `loss_after`.
+/// * `optimal_rate`: The `optimal_rate` property is the fraction of optimal moves found by the
+/// evaluation against the solver, if it ran this iteration.
+/// * `blunders`: The `blunders` property is the number of draw-to-loss blunders of that evaluation.
+/// * `value_mse`: The `value_mse` property is the value error of that evaluation.
+/// * `games_per_second`: The `games_per_second` property is the speed of self-play.
+/// * `seconds`: The `seconds` property is the duration of the iteration.
+/// * `elapsed`: The `elapsed` property is the time since training started or resumed, in seconds.
+/// * `stage`: The `stage` property is the stage of the curriculum the iteration was played at, if the
+/// run has a curriculum.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct IterationMetrics {
+    pub iteration: i16,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub mean_length: f64,
+    pub loss_before: f64,
+    pub loss_after: f64,
+    pub optimal_rate: Option<f64>,
+    pub blunders: Option<usize>,
+    pub value_mse: Option<f64>,
+    pub games_per_second: f64,
+    pub seconds: f64,
+    pub elapsed: f64,
+    pub stage: Option<Stage>,
+}
+
+impl IterationMetrics {
+    /// The function `new` summarizes the games of an iteration.
+    ///
+    /// Arguments:
+    ///
+    /// * `iteration`: The `iteration` parameter is the number of the iteration.
+    /// * `games`: The `games` parameter holds the metrics of its games.
+    /// * `accuracy`: The `accuracy` parameter is the evaluation run at its end, if any.
+    pub fn new(iteration: i16, games: &[GameMetrics], accuracy: Option<&Accuracy>) -> Self {
+        let count = games.len().max(1) as f64;
+        let mean = |value: fn(&GameMetrics) -> f64| games.iter().map(value).sum::<f64>() / count;
+        IterationMetrics {
+            iteration,
+            games: games.len(),
+            wins: games.iter().filter(|game| game.result > 0).count(),
+            draws: games.iter().filter(|game| game.result == 0).count(),
+            losses: games.iter().filter(|game| game.result < 0).count(),
+            mean_length: mean(|game| game.length as f64),
+            loss_before: mean(|game| game.loss_before),
+            loss_after: mean(|game| game.loss_after),
+            optimal_rate: accuracy.map(Accuracy::optimal_rate),
+            blunders: accuracy.map(|accuracy| accuracy.blunders),
+            value_mse: accuracy.map(|accuracy| accuracy.mse),
+            games_per_second: 0.0,
+            seconds: 0.0,
+            elapsed: 0.0,
+            stage: None,
+        }
+    }
+}
+
+/// The struct `PromotionMetrics` records the trainee moving on to the next stage of the curriculum.
+///
+/// Properties:
+///
+/// * `iteration`: The `iteration` property is the iteration after which the trainee was promoted.
+/// * `from`: The `from` property is the stage the trainee left.
+/// * `to`: The `to` property is the stage the trainee moved on to.
+/// * `score`: The `score` property is the trainee's score, draws counting half, over the games that
+/// earned the promotion.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct PromotionMetrics {
+    pub iteration: i16,
+    pub from: Stage,
+    pub to: Stage,
+    pub score: f64,
+}
+
+/// The struct `GateMetrics` records a match between the candidate and the champion of a gated run.
+///
+/// Properties:
+///
+/// * `iteration`: The `iteration` property is the iteration after which the match was played.
+/// * `wins`: The `wins` property is the number of games the candidate won.
+/// * `draws`: The `draws` property is the number of games drawn.
+/// * `losses`: The `losses` property is the number of games the candidate lost.
+/// * `score`: The `score` property is the candidate's score, draws counting half.
+/// * `promoted`: The `promoted` property tells whether the candidate replaced the champion.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GateMetrics {
+    pub iteration: i16,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub score: f64,
+    pub promoted: bool,
+}
+
+/// The enum `Metric` is a line of the metrics log, tagged with its `event` name.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub(crate) enum Metric {
+    Game(GameMetrics),
+    Iteration(IterationMetrics),
+    Promotion(PromotionMetrics),
+    Gate(GateMetrics),
+}
+
+/// The struct `MetricsLog` appends metrics to a JSON Lines file, one object per line.
+///
+/// Properties:
+///
+/// * `path`: The `path` property is the file being written.
+/// * `file`: The `file` property is the open file.
+pub(crate) struct MetricsLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl MetricsLog {
+    /// The function `open` opens the metrics log.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to write to.
+    /// * `resume`: The `resume` parameter is the iteration a resumed run continues from. The lines
+    /// already in the file are kept, except those of that iteration and later ones, which the
+    /// interrupted run wrote after its state was last saved and which are about to be written again.
+    /// With `None` the file is emptied for a new run.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn open(path: &Path, resume: Option<i16>) -> Result<Self> {
+        if let (Some(iteration), true) = (resume, path.is_file()) {
+            let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+            let kept: String = text
+                .lines()
+                .filter(|line| {
+                    let logged = serde_json::from_str::<serde_json::Value>(line).ok().and_then(|value| value["iteration"].as_i64());
+                    // A line cut short by the interruption does not parse and is dropped too.
+                    logged.is_some_and(|logged| logged < iteration as i64)
+                })
+                .flat_map(|line| [line, "\n"])
+                .collect();
+            fs::write(path, kept).map_err(|e| Error::io(path, e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume.is_some())
+            .truncate(resume.is_none())
+            .open(path)
+            .map_err(|e| Error::io(path, e))?;
+        Ok(MetricsLog { path: path.to_path_buf(), file })
+    }
+
+    /// The function `write` appends a metric to the log.
+    pub fn write(&mut self, metric: &Metric) -> Result<()> {
+        let mut line = serde_json::to_string(metric).map_err(|e| Error::Metrics { path: self.path.clone(), message: e.to_string() })?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).map_err(|e| Error::io(&self.path, e))
+    }
+}
@@ -1,33 +1,70 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
 use neuroflow::FeedForward;
 
-use crate::{bitboard_functions, board::Board};
+use crate::{bitboard_functions, board::Board, move_ordering::MoveOrdering, tablebase::Tablebase};
 
 pub(crate) struct Minimax{
     evaluater: FeedForward,
+    move_ordering: bool,
+    tablebase: Option<Arc<Tablebase>>,
 }
 
 impl Minimax{
-    /// The function `new` creates a new instance of a struct `Minimax` with an evaluator loaded from a
-    /// file.
+    /// The function `new` creates a new instance of a struct `Minimax` with the given evaluator.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `evaluater`: The `evaluater` parameter is the neural network used to score positions, usually
+    /// obtained from `Workspace::load_trainee`.
     /// 
     /// Returns:
     /// 
     /// A new instance of the `Minimax` struct is being returned.
-    pub fn new()-> Self{
-        let evaluater= neuroflow::io::load("src/Trainee/acordion.flow").unwrap();
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn new(evaluater: FeedForward)-> Self{
         Minimax{
-            evaluater: evaluater,
+            evaluater,
+            move_ordering: true,
+            tablebase: None,
         }
     }
 
-    /// This function sets the training mode with a specified trainer in Rust.
+    /// The function `set_move_ordering` turns the move ordering heuristics of `search` on or off, to
+    /// measure what they save.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `move_ordering`: The `move_ordering` parameter tells whether moves below the root are sorted by
+    /// `MoveOrdering`; otherwise they are searched cell by cell.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn set_move_ordering(&mut self, move_ordering: bool) {
+        self.move_ordering= move_ordering;
+    }
+
+    /// The function `set_tablebase` gives the search an endgame tablebase, whose exact results replace
+    /// the network at the leaves it covers.
     /// 
     /// Arguments:
     /// 
-    /// * `trainer`: The `trainer` parameter is a reference to a string that specifies the name or path
-    /// of the file containing the training data for the neural network model.
-    pub fn set_training_mode_with_type(&mut self, trainer: &str) {
-        self.evaluater= neuroflow::io::load(trainer).unwrap();
+    /// * `tablebase`: The `tablebase` parameter is the tablebase, or `None` to score every leaf with the
+    /// network.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<Tablebase>>) {
+        self.tablebase= tablebase;
+    }
+
+    /// The function `evaluate` scores a position with the neural network.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `board`: The `board` parameter is the position to be scored.
+    /// 
+    /// Returns:
+    /// 
+    /// The output of the network for the position, from the point of view of `my_board`.
+    pub fn evaluate(&mut self, board: &Board) -> f64 {
+        self.evaluater.activation(neuroflow::activators::Type::Tanh).calc(&board.get_board_in_array())[0]
     }
 
     /// The `minimax` function in Rust implements the minimax algorithm to determine the best move in a
@@ -44,31 +81,252 @@ impl Minimax{
     /// 
     /// The `minimax` function is returning the best move (an `i16` representing the index of the best
     /// move) based on the evaluation scores calculated for each possible move in the given board state.
-    pub fn minimax(&mut self, mut board: Board) -> i16{
-        let moves= bitboard_functions::get_indi_bits(board.gen_move());
-        if moves.len() == 1 {
-            return moves[0];
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn minimax(&mut self, board: Board) -> i16{
+        self.analyse(&board, 0).best_move
+    }
+
+    /// The function `analyse` searches a position `depth` moves past each of its legal moves, scoring
+    /// finished games exactly and the positions at the horizon with the tablebase, if one was given and
+    /// holds them, or else with the neural network.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `board`: The `board` parameter is the position to search.
+    /// * `depth`: The `depth` parameter is the number of moves to look ahead after each legal move; 0
+    /// scores the positions they lead to with the network, as `minimax` does.
+    /// 
+    /// Returns:
+    /// 
+    /// The `SearchResult` of the search, with every legal move scored.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn analyse(&mut self, board: &Board, depth: u8) -> SearchResult {
+        self.search(board, SearchLimits { depth: Some(depth), ..SearchLimits::default() })
+    }
+
+    /// The function `search` searches a position with iterative deepening: one move past each legal
+    /// move, then two, and so on until a limit is reached or the game tree is exhausted. Each iteration
+    /// searches the moves in the order of the scores of the previous one, its principal variation
+    /// first, and alpha-beta pruning cuts off the rest of the tree below the root.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `board`: The `board` parameter is the position to search.
+    /// * `limits`: The `limits` parameter bounds the search. The first iteration always completes.
+    /// 
+    /// Returns:
+    /// 
+    /// The `SearchResult` of the last iteration that completed, with the nodes and time of the whole
+    /// search.
+    pub fn search(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        let mut state= SearchState {
+            started: Instant::now(),
+            nodes: 0,
+            limits,
+            enforced: false,
+            ordering: self.move_ordering.then(MoveOrdering::new),
+        };
+        let (me, opp)= board.get_sides();
+        let empty= !(me | opp) & Board::BOARD;
+        let mut result= SearchResult::new(0);
+        if Board::has_line(opp) {
+            result.elapsed= state.started.elapsed();
+            return result;
+        }
+        // Past the last empty cell every iteration finds the same scores.
+        let deepest= limits.depth.unwrap_or(u8::MAX).min((empty.count_ones() as u8).saturating_sub(1));
+        let mut order= bitboard_functions::get_indi_bits(empty);
+        for depth in 0..=deepest {
+            state.enforced= depth > 0;
+            let Some(iteration)= self.search_root(board, depth, &order, &result.principal_variation, &mut state) else {
+                result.stopped= true;
+                break;
+            };
+            let mut scored= iteration.moves.clone();
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+            order= scored.into_iter().map(|(_move, _)| _move).collect();
+            result= iteration;
+        }
+        result.nodes= state.nodes;
+        result.elapsed= state.started.elapsed();
+        result
+    }
+
+    /// The function `search_root` runs an iteration of `search`, scoring every legal move with a full
+    /// window.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `board`: The `board` parameter is the position to search.
+    /// * `depth`: The `depth` parameter is the number of moves to look ahead after each legal move.
+    /// * `order`: The `order` parameter lists the legal moves in the order they are searched.
+    /// * `pv`: The `pv` parameter is the principal variation of the previous iteration.
+    /// * `state`: The `state` parameter counts the nodes, stops the iteration at the limits and orders the
+    /// moves.
+    /// 
+    /// Returns:
+    /// 
+    /// The result of the iteration, or `None` if a limit was reached before it completed.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn search_root(&mut self, board: &Board, depth: u8, order: &[i16], pv: &[i16], state: &mut SearchState) -> Option<SearchResult> {
+        let mut board= board.clone();
+        let mut result= SearchResult::new(depth);
+        for &i in order {
+            let hint= if pv.first() == Some(&i) { &pv[1..] } else { &[] };
+            board.play_move(i);
+            let searched= self.negamax(&mut board, depth, f64::NEG_INFINITY, f64::INFINITY, hint, state);
+            board.undo_move(i);
+            let (value, line)= searched?;
+            let score= -value;
+            if result.moves.is_empty() || score > result.score {
+                result.best_move= i;
+                result.score= score;
+                result.principal_variation= std::iter::once(i).chain(line).collect();
+            }
+            result.moves.push((i, score));
+        }
+        Some(result)
+    }
+
+    /// The function `negamax` searches the position `board` `depth` moves deep with alpha-beta
+    /// pruning, trying the first move of `hint` before the others, sorted by the move ordering if any.
+    /// 
+    /// Returns:
+    /// 
+    /// The value of the position for the side to move, from -1 for a loss to 1 for a win, and the moves
+    /// of the principal variation from it, or `None` if a limit of the search was reached.
+    fn negamax(&mut self, board: &mut Board, depth: u8, mut alpha: f64, beta: f64, hint: &[i16], state: &mut SearchState) -> Option<(f64, Vec<i16>)> {
+        if state.exhausted() {
+            return None;
+        }
+        state.nodes += 1;
+        let (me, opp)= board.get_sides();
+        if Board::has_line(opp) {
+            return Some((-1.0, Vec::new()));
         }
-        let mut best_move: i16= 0;
-        let mut best_move_score: f64 =0.0;
-        let mut is_first= true;
+        if me | opp == Board::BOARD {
+            return Some((0.0, Vec::new()));
+        }
+        if depth == 0 {
+            if let Some(result)= self.tablebase.as_ref().and_then(|tablebase| tablebase.probe(me as u64, opp as u64)) {
+                return Some((result as f64, Vec::new()));
+            }
+            return Some((self.evaluate(board), Vec::new()));
+        }
+        let mut moves= bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD);
+        if let Some(ordering)= &state.ordering {
+            ordering.order(me, opp, &mut moves);
+        }
+        if let Some(first)= hint.first().and_then(|first| moves.iter().position(|_move| _move == first)) {
+            moves[..=first].rotate_right(1);
+        }
+        let mut best= (f64::NEG_INFINITY, Vec::new());
         for i in moves {
+            let child_hint= if hint.first() == Some(&i) { &hint[1..] } else { &[] };
             board.play_move(i);
-            let score= -self.evaluater.activation(neuroflow::activators::Type::Tanh).calc(&(*board.get_board_in_array().into_boxed_slice()))[0];
+            let searched= self.negamax(board, depth - 1, -beta, -alpha, child_hint, state);
             board.undo_move(i);
-            if is_first {
-                is_first = false;
-                best_move_score= score;
-                best_move= i;
-                continue;
+            let (value, line)= searched?;
+            if -value > best.0 {
+                best= (-value, std::iter::once(i).chain(line).collect());
             }
-            if score > best_move_score {
-                best_move= i;
-                best_move_score = score;
+            alpha= alpha.max(-value);
+            if alpha >= beta {
+                if let Some(ordering)= &mut state.ordering {
+                    ordering.record_cutoff(me, opp, i, depth);
+                }
+                break;
             }
         }
-        return best_move;
+        Some(best)
     }
-    
 }
 
+/// The struct `SearchResult` is what a search of `Minimax` found in a position.
+/// 
+/// Properties:
+/// 
+/// * `moves`: The `moves` property pairs every legal move with its score, in the order they were
+/// searched.
+/// * `best_move`: The `best_move` property is the move with the highest score, the first one on ties,
+/// or 0 if the game is over.
+/// * `score`: The `score` property is the score of the best move, from -1 for a loss to 1 for a win
+/// for the side to move.
+/// * `principal_variation`: The `principal_variation` property is the line of play expected from the
+/// position, starting with the best move.
+/// * `depth`: The `depth` property is the number of moves searched past each legal move.
+/// * `nodes`: The `nodes` property is the number of positions visited.
+/// * `elapsed`: The `elapsed` property is the time the search took.
+/// * `stopped`: The `stopped` property tells whether a limit stopped the search before it reached the
+/// depth it was allowed.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug)]
+pub(crate) struct SearchResult {
+    pub moves: Vec<(i16, f64)>,
+    pub best_move: i16,
+    pub score: f64,
+    pub principal_variation: Vec<i16>,
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub stopped: bool,
+}
+
+impl SearchResult {
+    /// The function `new` creates the result of a search to `depth` that has not scored any move yet.
+    fn new(depth: u8) -> Self {
+        SearchResult {
+            moves: Vec::new(),
+            best_move: 0,
+            score: 0.0,
+            principal_variation: Vec::new(),
+            depth,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            stopped: false,
+        }
+    }
+}
+
+/// The struct `SearchLimits` bounds a search of `Minimax::search`; a limit left to `None` does not
+/// apply.
+/// 
+/// Properties:
+/// 
+/// * `depth`: The `depth` property is the deepest iteration, in moves past each legal move.
+/// * `nodes`: The `nodes` property is the number of positions the search may visit.
+/// * `time`: The `time` property is the time the search may take.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// The struct `SearchState` is what a search carries from node to node.
+/// 
+/// Properties:
+/// 
+/// * `started`: The `started` property is when the search started.
+/// * `nodes`: The `nodes` property is the number of positions visited.
+/// * `limits`: The `limits` property bounds the search.
+/// * `enforced`: The `enforced` property is unset while the first iteration runs, which always
+/// completes.
+/// * `ordering`: The `ordering` property sorts the moves below the root, unless move ordering is off.
+#[allow(clippy::doc_lazy_continuation)]
+struct SearchState {
+    started: Instant,
+    nodes: u64,
+    limits: SearchLimits,
+    enforced: bool,
+    ordering: Option<MoveOrdering>,
+}
+
+impl SearchState {
+    /// The function `exhausted` tells whether the search has to stop.
+    fn exhausted(&self) -> bool {
+        self.enforced
+            && (self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+                || self.limits.time.is_some_and(|time| self.started.elapsed() >= time))
+    }
+}
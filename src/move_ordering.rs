@@ -0,0 +1,97 @@
+use crate::board::Board;
+
+/// The score of a move that completes a line of the side to move.
+const WIN: u32 = 1 << 30;
+/// The score of a move that stops the opponent from completing a line.
+const BLOCK: u32 = 1 << 29;
+/// The score of the first killer move of a ply; the second one scores one less.
+const KILLER: u32 = 1 << 28;
+/// How much each cell is preferred when nothing else tells the moves apart: the center, then the
+/// corners, then the sides.
+const CELL_PREFERENCE: [u32; 9] = [2, 1, 2, 1, 3, 1, 2, 1, 2];
+
+/// The struct `MoveOrdering` sorts the moves of a search so that alpha-beta can cut off as much of
+/// the tree as possible: immediate wins first, then blocks of the opponent's threats, the killer moves
+/// of the ply, and the remaining moves by their history and their cell.
+///
+/// Properties:
+///
+/// * `killers`: The `killers` property holds, for each number of stones on the board, the last two
+/// moves that caused a cutoff there.
+/// * `history`: The `history` property scores each cell, for each side to move, by the cutoffs it
+/// caused, weighted by the square of the remaining depth.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct MoveOrdering {
+    killers: [[i16; 2]; 10],
+    history: [[u32; 9]; 2],
+}
+
+impl MoveOrdering {
+    /// The function `new` creates an ordering that knows of no cutoffs yet.
+    pub fn new() -> Self {
+        MoveOrdering {
+            killers: [[0; 2]; 10],
+            history: [[0; 9]; 2],
+        }
+    }
+
+    /// The function `order` sorts the legal moves of a position, best first.
+    ///
+    /// Arguments:
+    ///
+    /// * `me`: The `me` parameter is the bitboard of the side to move.
+    /// * `opp`: The `opp` parameter is the bitboard of the other side.
+    /// * `moves`: The `moves` parameter holds the moves to sort.
+    pub fn order(&self, me: i16, opp: i16, moves: &mut [i16]) {
+        let wins = Board::line_completions(me, opp);
+        let blocks = Board::line_completions(opp, me);
+        moves.sort_by_key(|_move| std::cmp::Reverse(self.score(me | opp, wins, blocks, *_move)));
+    }
+
+    /// The function `record_cutoff` remembers a move that caused a beta cutoff.
+    ///
+    /// Arguments:
+    ///
+    /// * `me`: The `me` parameter is the bitboard of the side that played the move.
+    /// * `opp`: The `opp` parameter is the bitboard of the other side.
+    /// * `_move`: The `_move` parameter is the move.
+    /// * `depth`: The `depth` parameter is the depth that was left to search below the position.
+    pub fn record_cutoff(&mut self, me: i16, opp: i16, _move: i16, depth: u8) {
+        if (Board::line_completions(me, opp) | Board::line_completions(opp, me)) & _move != 0 {
+            // Wins and blocks are found without help.
+            return;
+        }
+        let stones = (me | opp).count_ones() as usize;
+        let killers = &mut self.killers[stones];
+        if killers[0] != _move {
+            killers[1] = killers[0];
+            killers[0] = _move;
+        }
+        self.history[stones % 2][_move.trailing_zeros() as usize] += depth as u32 * depth as u32;
+    }
+
+    /// The function `score` rates a move for `order`.
+    ///
+    /// Arguments:
+    ///
+    /// * `occupied`: The `occupied` parameter is the bitboard of the stones of both sides.
+    /// * `wins`: The `wins` parameter is the bitboard of the winning squares of the side to move.
+    /// * `blocks`: The `blocks` parameter is the bitboard of the winning squares of the other side.
+    /// * `_move`: The `_move` parameter is the move to rate.
+    fn score(&self, occupied: i16, wins: i16, blocks: i16, _move: i16) -> u32 {
+        let stones = occupied.count_ones() as usize;
+        let cell = _move.trailing_zeros() as usize;
+        let mut score = self.history[stones % 2][cell] + CELL_PREFERENCE[cell];
+        if wins & _move != 0 {
+            score += WIN;
+        } else if blocks & _move != 0 {
+            score += BLOCK;
+        } else if self.killers[stones][0] == _move {
+            score += KILLER;
+        } else if self.killers[stones][1] == _move {
+            score += KILLER - 1;
+        }
+        score
+    }
+}
+
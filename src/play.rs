@@ -0,0 +1,571 @@
+use std::time::Duration;
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{board::Board, error::{Error, Result}, minimax::Minimax, ratings, solver::Solver, verdict, workspace::Workspace};
+
+/// How long the game waits for a key press before redrawing.
+const FRAME: Duration = Duration::from_millis(250);
+/// The width of a cell on screen, borders included.
+const CELL_WIDTH: u16 = 11;
+/// The height of a cell on screen, borders included.
+const CELL_HEIGHT: u16 = 5;
+/// How many moves the search of the move analysis looks ahead after each move.
+const SEARCH_DEPTH: u8 = 2;
+
+/// The enum `Heat` selects the value the heat map colors the empty cells by.
+///
+/// Variants:
+///
+/// * `Network`: The value the trainee's network gives to the move.
+/// * `Search`: The value of the move after a short search with the network at the horizon.
+/// * `Solver`: The value of the move under perfect play.
+#[derive(Clone, Copy, PartialEq)]
+enum Heat {
+    Network,
+    Search,
+    Solver,
+}
+
+impl Heat {
+    /// The function `next` returns the heat map shown after this one, or `None` to turn it off.
+    fn next(heat: Option<Heat>) -> Option<Heat> {
+        match heat {
+            None => Some(Heat::Network),
+            Some(Heat::Network) => Some(Heat::Search),
+            Some(Heat::Search) => Some(Heat::Solver),
+            Some(Heat::Solver) => None,
+        }
+    }
+
+    /// The function `name` describes the heat map to the user.
+    fn name(self) -> &'static str {
+        match self {
+            Heat::Network => "network value",
+            Heat::Search => "search value",
+            Heat::Solver => "solver verdict",
+        }
+    }
+}
+
+/// The struct `MoveAnalysis` is the engine's opinion of a legal move of the user, every value being
+/// from the user's point of view.
+///
+/// Properties:
+///
+/// * `network`: The `network` property is the value the trainee's network gives to the move.
+/// * `search`: The `search` property is the value of the move searched `SEARCH_DEPTH` moves deeper.
+/// * `verdict`: The `verdict` property is the value of the move under perfect play: 1, 0 or -1.
+struct MoveAnalysis {
+    network: f64,
+    search: f64,
+    verdict: i8,
+}
+
+impl MoveAnalysis {
+    /// The function `value` returns the value the heat map `heat` colors the move by.
+    fn value(&self, heat: Heat) -> f64 {
+        match heat {
+            Heat::Network => self.network,
+            Heat::Search => self.search,
+            Heat::Solver => self.verdict as f64,
+        }
+    }
+}
+
+/// The struct `Game` is a game between the user and the trainee, kept as its list of moves so that
+/// moves can be taken back.
+///
+/// Properties:
+///
+/// * `moves`: The `moves` property lists the moves played, X first.
+/// * `human_x`: The `human_x` property tells whether the user plays X.
+/// * `assisted`: The `assisted` property is set once the user took a move back or asked for a hint;
+/// such games are not rated.
+/// * `finished`: The `finished` property is set once the end of the game has been recorded.
+#[allow(clippy::doc_lazy_continuation)]
+struct Game {
+    moves: Vec<i16>,
+    human_x: bool,
+    assisted: bool,
+    finished: bool,
+}
+
+impl Game {
+    /// The function `new` starts a game.
+    fn new(human_x: bool) -> Self {
+        Game {
+            moves: Vec::new(),
+            human_x,
+            assisted: false,
+            finished: false,
+        }
+    }
+
+    /// The function `stones` returns the bitboards of X and of O.
+    fn stones(&self) -> (i16, i16) {
+        self.moves.iter().enumerate().fold((0, 0), |(x, o), (i, _move)| {
+            if i % 2 == 0 {
+                (x | _move, o)
+            } else {
+                (x, o | _move)
+            }
+        })
+    }
+
+    /// The function `board_for` returns the position as seen by one side, the way the trainer hands
+    /// boards to its players.
+    ///
+    /// Arguments:
+    ///
+    /// * `x_side`: The `x_side` parameter tells whether the board is seen by X.
+    fn board_for(&self, x_side: bool) -> Board {
+        let mut board = Board::new();
+        if !x_side {
+            board.change_type();
+        }
+        for _move in &self.moves {
+            board.play_move(*_move);
+        }
+        board
+    }
+
+    /// The function `human_to_move` tells whether it is the user's turn.
+    fn human_to_move(&self) -> bool {
+        self.moves.len().is_multiple_of(2) == self.human_x
+    }
+
+    /// The function `winning_line` returns the cells of the completed line, or 0 if there is none.
+    fn winning_line(&self) -> i16 {
+        let (x, o) = self.stones();
+        Board::completed_lines(x) | Board::completed_lines(o)
+    }
+
+    /// The function `is_over` tells whether the game has ended.
+    fn is_over(&self) -> bool {
+        self.winning_line() != 0 || self.moves.len() == 9
+    }
+
+    /// The function `human_score` returns the user's score once the game is over: 1, 0.5 or 0.
+    fn human_score(&self) -> Option<f64> {
+        if !self.is_over() {
+            return None;
+        }
+        if self.winning_line() == 0 {
+            return Some(0.5);
+        }
+        // The side that made the last move completed the line.
+        let x_won = self.moves.len() % 2 == 1;
+        Some(if x_won == self.human_x { 1.0 } else { 0.0 })
+    }
+}
+
+/// The struct `Session` counts the user's results over the games of a `play` session.
+#[derive(Default)]
+pub(crate) struct Session {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// The struct `PlayUi` is the state of the terminal game.
+///
+/// Properties:
+///
+/// * `workspace`: The `workspace` property is the run directory the ratings are kept in.
+/// * `mm`: The `mm` property is the trainee.
+/// * `game`: The `game` property is the game being played.
+/// * `cursor`: The `cursor` property is the selected cell, row by row from 0 to 8.
+/// * `hint`: The `hint` property is the move suggested by the engine, if one was asked for.
+/// * `solver`: The `solver` property gives the verdicts of the move analysis.
+/// * `heat`: The `heat` property is the heat map shown over the empty cells, if any.
+/// * `analysis`: The `analysis` property pairs each legal move of the user with its analysis while
+/// the heat map is shown.
+/// * `analysed`: The `analysed` property is the position `analysis` belongs to, as the stones of the
+/// user and of the trainee, so that it is only computed again once the position changes.
+/// * `status`: The `status` property is the message shown to the user.
+/// * `session`: The `session` property counts the results of the finished games.
+#[allow(clippy::doc_lazy_continuation)]
+struct PlayUi<'a> {
+    workspace: &'a Workspace,
+    mm: Minimax,
+    game: Game,
+    cursor: usize,
+    hint: Option<i16>,
+    solver: Solver,
+    heat: Option<Heat>,
+    analysis: Vec<(i16, MoveAnalysis)>,
+    analysed: Option<(i16, i16)>,
+    status: String,
+    session: Session,
+}
+
+/// The function `run` plays games against the trainee in the terminal until the user quits.
+///
+/// Arguments:
+///
+/// * `workspace`: The `workspace` parameter is the run directory the trainee is loaded from and whose
+/// ratings are updated after every unassisted game.
+/// * `human_x`: The `human_x` parameter tells whether the user plays X in the first game.
+///
+/// Returns:
+///
+/// The results of the session, or the error raised while loading the trainee, saving the ratings or
+/// drawing on the terminal.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) fn run(workspace: &Workspace, human_x: bool) -> Result<Session> {
+    let mut ui = PlayUi {
+        workspace,
+        mm: workspace.trainee_search()?,
+        game: Game::new(human_x),
+        cursor: 4,
+        hint: None,
+        solver: Solver::new(),
+        heat: None,
+        analysis: Vec::new(),
+        analysed: None,
+        status: String::new(),
+        session: Session::default(),
+    };
+    let mut terminal = ratatui::try_init().map_err(Error::terminal)?;
+    ui.new_game(human_x)?;
+    let result = ui.show(&mut terminal);
+    ratatui::restore();
+    result?;
+    Ok(ui.session)
+}
+
+impl PlayUi<'_> {
+    /// The function `show` draws the game and handles the keys until the user quits.
+    fn show(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame)).map_err(Error::terminal)?;
+            if !event::poll(FRAME).map_err(Error::terminal)? {
+                continue;
+            }
+            let Event::Key(key) = event::read().map_err(Error::terminal)? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Up => self.cursor = if self.cursor >= 3 { self.cursor - 3 } else { self.cursor },
+                KeyCode::Down => self.cursor = if self.cursor < 6 { self.cursor + 3 } else { self.cursor },
+                KeyCode::Left => self.cursor = if !self.cursor.is_multiple_of(3) { self.cursor - 1 } else { self.cursor },
+                KeyCode::Right => self.cursor = if self.cursor % 3 < 2 { self.cursor + 1 } else { self.cursor },
+                KeyCode::Enter | KeyCode::Char(' ') => self.play_human(self.cursor)?,
+                KeyCode::Char(digit @ '1'..='9') => {
+                    // Numeric keypad layout: 7 8 9 is the top row.
+                    let d = digit as usize - '1' as usize;
+                    self.cursor = (2 - d / 3) * 3 + d % 3;
+                    self.play_human(self.cursor)?;
+                }
+                KeyCode::Char('u') => self.undo(),
+                KeyCode::Char('h') => self.show_hint(),
+                KeyCode::Char('a') => self.heat = Heat::next(self.heat),
+                KeyCode::Char('n') => self.new_game(self.game.human_x)?,
+                KeyCode::Char('s') => self.new_game(!self.game.human_x)?,
+                _ => {}
+            }
+            self.analyse();
+        }
+    }
+
+    /// The function `new_game` starts a game, letting the trainee open if the user plays O.
+    fn new_game(&mut self, human_x: bool) -> Result<()> {
+        self.game = Game::new(human_x);
+        self.hint = None;
+        self.cursor = 4;
+        self.status = format!("New game: you play {}.", if human_x { "X" } else { "O" });
+        if !human_x {
+            self.play_engine()?;
+        }
+        Ok(())
+    }
+
+    /// The function `play_human` plays the user's move on `cell` and the trainee's answer.
+    fn play_human(&mut self, cell: usize) -> Result<()> {
+        if self.game.is_over() {
+            self.status = "The game is over: press n for a rematch or s to swap sides.".to_string();
+            return Ok(());
+        }
+        let _move = 1 << cell;
+        let (x, o) = self.game.stones();
+        if (x | o) & _move != 0 {
+            self.status = "That cell is taken.".to_string();
+            return Ok(());
+        }
+        self.game.moves.push(_move);
+        self.hint = None;
+        self.status = String::new();
+        if !self.check_end()? {
+            self.play_engine()?;
+        }
+        Ok(())
+    }
+
+    /// The function `play_engine` plays the trainee's move.
+    fn play_engine(&mut self) -> Result<()> {
+        let _move = self.mm.minimax(self.game.board_for(!self.game.human_x));
+        self.game.moves.push(_move);
+        self.check_end()?;
+        Ok(())
+    }
+
+    /// The function `check_end` records the result once the game is over, rating it unless the user
+    /// was assisted.
+    ///
+    /// Returns:
+    ///
+    /// Whether the game is over.
+    fn check_end(&mut self) -> Result<bool> {
+        let Some(score) = self.game.human_score() else {
+            return Ok(false);
+        };
+        if self.game.finished {
+            return Ok(true);
+        }
+        self.game.finished = true;
+        let verdict = if score > 0.5 {
+            self.session.wins += 1;
+            "You won!"
+        } else if score < 0.5 {
+            self.session.losses += 1;
+            "The trainee won."
+        } else {
+            self.session.draws += 1;
+            "It is a draw."
+        };
+        if self.game.assisted {
+            self.status = format!("{} (not rated: takebacks or hints were used)", verdict);
+        } else {
+            let mut ratings = self.workspace.load_ratings()?;
+            ratings.record(ratings::HUMAN, ratings::TRAINEE, score);
+            self.workspace.save_ratings(&ratings)?;
+            self.status = format!(
+                "{} Ratings: you {:.0}, trainee {:.0}",
+                verdict,
+                ratings.get(ratings::HUMAN).elo,
+                ratings.get(ratings::TRAINEE).elo
+            );
+        }
+        Ok(true)
+    }
+
+    /// The function `undo` takes back the user's last move and the trainee's answer to it.
+    fn undo(&mut self) {
+        if self.game.is_over() {
+            self.status = "The game is over: press n for a rematch or s to swap sides.".to_string();
+            return;
+        }
+        let human_moves = (0..self.game.moves.len()).filter(|i| (i % 2 == 0) == self.game.human_x).count();
+        if human_moves == 0 {
+            self.status = "Nothing to take back.".to_string();
+            return;
+        }
+        while self.game.moves.pop().is_some() {
+            if self.game.moves.len().is_multiple_of(2) == self.game.human_x {
+                break;
+            }
+        }
+        self.game.assisted = true;
+        self.hint = None;
+        self.status = "Move taken back.".to_string();
+    }
+
+    /// The function `show_hint` asks the trainee which move it would play in the user's place.
+    fn show_hint(&mut self) {
+        if self.game.is_over() || !self.game.human_to_move() {
+            return;
+        }
+        let hint = self.mm.minimax(self.game.board_for(self.game.human_x));
+        self.hint = Some(hint);
+        self.cursor = hint.trailing_zeros() as usize;
+        self.game.assisted = true;
+        self.status = "The trainee would play the highlighted cell.".to_string();
+    }
+
+    /// The function `analyse` analyses the user's moves while the heat map is shown and it is the
+    /// user's turn, once per position rather than after every key. Seeing the analysis counts as being
+    /// assisted.
+    fn analyse(&mut self) {
+        if self.heat.is_none() || self.game.is_over() || !self.game.human_to_move() {
+            self.analysis.clear();
+            self.analysed = None;
+            return;
+        }
+        self.game.assisted = true;
+        let board = self.game.board_for(self.game.human_x);
+        if self.analysed == Some(board.get_sides()) {
+            return;
+        }
+        let verdicts = self.solver.score_moves(&board);
+        // The search orders the moves by their scores, so the lists are matched by move.
+        let searched = self.mm.analyse(&board, SEARCH_DEPTH).moves;
+        self.analysis = self
+            .mm
+            .analyse(&board, 0)
+            .moves
+            .into_iter()
+            .map(|(_move, network)| {
+                let analysis = MoveAnalysis {
+                    network,
+                    search: searched.iter().find(|(cell, _)| *cell == _move).map_or(network, |(_, search)| *search),
+                    verdict: verdicts.iter().find(|(cell, _)| *cell == _move).map_or(0, |(_, verdict)| *verdict),
+                };
+                (_move, analysis)
+            })
+            .collect();
+        self.analysed = Some(board.get_sides());
+    }
+
+    /// The function `draw` renders the board and the side panel.
+    fn draw(&self, frame: &mut Frame) {
+        let [board, panel] = Layout::horizontal([Constraint::Length(3 * CELL_WIDTH + 2), Constraint::Min(30)]).areas(frame.area());
+        let board = Rect { height: board.height.min(3 * CELL_HEIGHT + 2), ..board };
+        let block = Block::default().borders(Borders::ALL).title("Acordion");
+        let inner = block.inner(board);
+        frame.render_widget(block, board);
+        for cell in 0..9 {
+            let area = Rect {
+                x: inner.x + (cell % 3) as u16 * CELL_WIDTH,
+                y: inner.y + (cell / 3) as u16 * CELL_HEIGHT,
+                width: CELL_WIDTH,
+                height: CELL_HEIGHT,
+            }
+            .intersection(inner);
+            self.draw_cell(frame, area, cell);
+        }
+        self.draw_panel(frame, panel);
+    }
+
+    /// The function `draw_cell` renders a cell: its stone, or its keypad digit when empty, with the
+    /// cursor, the last move, the hint and the winning line highlighted.
+    fn draw_cell(&self, frame: &mut Frame, area: Rect, cell: usize) {
+        let bit = 1 << cell;
+        let (x, o) = self.game.stones();
+        let (text, mut style) = if x & bit != 0 {
+            ("X".to_string(), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        } else if o & bit != 0 {
+            ("O".to_string(), Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD))
+        } else if self.hint == Some(bit) {
+            ("?".to_string(), Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))
+        } else {
+            (keypad_digit(cell).to_string(), Style::default().fg(Color::DarkGray))
+        };
+        if self.game.winning_line() & bit != 0 {
+            style = style.bg(Color::Green);
+        }
+        let analysis = self.analysis.iter().find(|(_move, _)| *_move == bit).map(|(_, analysis)| analysis);
+        if let (Some(heat), Some(analysis)) = (self.heat, analysis) {
+            style = Style::default().fg(Color::White).bg(heat_color(analysis.value(heat)));
+        }
+
+        let border = if cell == self.cursor && !self.game.is_over() {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else if self.hint == Some(bit) {
+            Style::default().fg(Color::Magenta)
+        } else if self.game.moves.last() == Some(&bit) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let block = Block::default().borders(Borders::ALL).border_style(border).style(Style::default().bg(style.bg.unwrap_or(Color::Reset)));
+        let lines = match analysis {
+            Some(analysis) => vec![
+                Line::styled(format!("N {:+.2}", analysis.network), style),
+                Line::styled(format!("S {:+.2}", analysis.search), style),
+                Line::styled(verdict(analysis.verdict), style.add_modifier(Modifier::BOLD)),
+            ],
+            None => {
+                let padding = area.height.saturating_sub(3) / 2;
+                let mut lines = vec![Line::raw(""); padding as usize];
+                lines.push(Line::styled(text, style));
+                lines
+            }
+        };
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center).block(block), area);
+    }
+
+    /// The function `draw_panel` renders the status, the session score and the keys.
+    fn draw_panel(&self, frame: &mut Frame, area: Rect) {
+        let you = if self.game.human_x { "X (you move first)" } else { "O (the trainee moves first)" };
+        let turn = if self.game.is_over() {
+            "Game over"
+        } else if self.game.human_to_move() {
+            "Your move"
+        } else {
+            "Trainee to move"
+        };
+        let mut lines = vec![
+            Line::raw(format!("You play {}", you)),
+            Line::styled(turn, Style::default().add_modifier(Modifier::BOLD)),
+            Line::raw(self.status.clone()),
+            Line::raw(""),
+            Line::raw(format!("Session: won {}, drawn {}, lost {}", self.session.wins, self.session.draws, self.session.losses)),
+            Line::raw(if self.game.assisted { "This game is not rated." } else { "This game is rated." }),
+            Line::raw(""),
+            Line::raw(match self.heat {
+                Some(heat) => format!("Heat map: {} (N network, S search, solver verdict)", heat.name()),
+                None => "Heat map: off".to_string(),
+            }),
+        ];
+        if !self.analysis.is_empty() {
+            let board = self.game.board_for(self.game.human_x);
+            let (wins, blocks) = board.winning_squares();
+            let (forks, trainee_forks) = board.forks();
+            let (open, trainee_open) = board.open_lines();
+            lines.push(Line::raw(format!("Win on: {}   block: {}", keypad_cells(wins), keypad_cells(blocks))));
+            lines.push(Line::raw(format!("Forks: {}   trainee forks: {}", keypad_cells(forks), keypad_cells(trainee_forks))));
+            lines.push(Line::raw(format!("Open lines: you {}, trainee {}", open, trainee_open)));
+        }
+        lines.extend([
+            Line::raw(""),
+            Line::styled("Keys", Style::default().add_modifier(Modifier::BOLD)),
+            Line::raw("arrows   move the cursor"),
+            Line::raw("enter    play the cell under the cursor"),
+            Line::raw("1-9      play a cell, laid out like a keypad"),
+            Line::raw("u        take back your last move"),
+            Line::raw("h        hint from the trainee"),
+            Line::raw("a        heat map: network, search, solver, off"),
+            Line::raw("n        rematch"),
+            Line::raw("s        swap sides and start again"),
+            Line::raw("q        quit"),
+        ]);
+        let panel = Paragraph::new(lines).wrap(Wrap { trim: false }).block(Block::default().borders(Borders::ALL).title("Game"));
+        frame.render_widget(panel, area);
+    }
+}
+
+/// The function `heat_color` maps a value from -1 (red) to 1 (green) to the background of a cell.
+fn heat_color(value: f64) -> Color {
+    let t = ((value.clamp(-1.0, 1.0) + 1.0) / 2.0) as f32;
+    Color::Rgb((40.0 + 160.0 * (1.0 - t)) as u8, (40.0 + 140.0 * t) as u8, 40)
+}
+
+/// The function `keypad_digit` returns the key of a cell on a numeric keypad, where 7 8 9 is the top
+/// row.
+fn keypad_digit(cell: usize) -> usize {
+    7 - 3 * (cell / 3) + cell % 3
+}
+
+/// The function `keypad_cells` lists the cells of a bitboard by their keypad digits, or `-` if it is
+/// empty.
+fn keypad_cells(cells: i16) -> String {
+    if cells == 0 {
+        return "-".to_string();
+    }
+    let mut digits: Vec<usize> = (0..9).filter(|cell| cells & 1 << cell != 0).map(keypad_digit).collect();
+    digits.sort();
+    digits.iter().map(|digit| digit.to_string()).collect::<Vec<_>>().join(" ")
+}
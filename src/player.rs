@@ -0,0 +1,335 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use rand::{seq::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitboard_functions,
+    board::Board,
+    heuristic::Heuristic,
+    mcts::Mcts,
+    minimax::{Minimax, SearchLimits},
+    print_board,
+    qlearning::QLearning,
+    solver::Solver,
+};
+
+/// The struct `Clock` is the thinking time a player has left for the rest of a game.
+///
+/// Properties:
+///
+/// * `remaining`: The `remaining` property is the time left, or `None` if the game is not timed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Clock {
+    pub remaining: Option<Duration>,
+}
+
+impl Clock {
+    /// The function `move_time` splits the time left evenly between the moves the player still has
+    /// to make in a position.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position the player is to move in.
+    pub fn move_time(&self, board: &Board) -> Option<Duration> {
+        let (me, opp) = board.get_sides();
+        let moves = (!(me | opp) & Board::BOARD).count_ones().div_ceil(2);
+        self.remaining.map(|remaining| remaining / moves.max(1))
+    }
+}
+
+/// The trait `Player` is anything that can play a game: the user, a network, a search or a scripted
+/// strategy.
+pub(crate) trait Player {
+    /// The function `choose_move` picks the move of the player.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position seen from the player's side, which is not
+    /// over.
+    /// * `clock`: The `clock` parameter is the time the player has left.
+    /// * `rng`: The `rng` parameter breaks ties and drives the random choices of the player.
+    ///
+    /// Returns:
+    ///
+    /// The move, or `None` if the player resigns.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn choose_move(&mut self, board: &Board, clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16>;
+
+    /// The function `observe_result` tells the player how a game ended.
+    ///
+    /// Arguments:
+    ///
+    /// * `score`: The `score` parameter is the player's score: 1 for a win, 0.5 for a draw, 0 for a
+    /// loss.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn observe_result(&mut self, _score: f64) {}
+}
+
+/// The struct `MatchRecord` is a game played by `play_match`.
+///
+/// Properties:
+///
+/// * `moves`: The `moves` property lists the moves of the game, X first, opening included.
+/// * `result`: The `result` property is the result for X: 1 for a win, 0 for a draw and -1 for a loss.
+#[derive(Clone, Debug)]
+pub(crate) struct MatchRecord {
+    pub moves: Vec<i16>,
+    pub result: i8,
+}
+
+impl MatchRecord {
+    /// The function `x_score` returns the score of X: 1, 0.5 or 0.
+    pub fn x_score(&self) -> f64 {
+        (self.result as f64 + 1.0) / 2.0
+    }
+}
+
+/// The function `play_match` plays a game between two players, keeping a board for each side like
+/// `Trainer::play_game_with_type_1`, and tells both of them the result.
+///
+/// Arguments:
+///
+/// * `x`: The `x` parameter is the player moving first.
+/// * `o`: The `o` parameter is the player moving second.
+/// * `opening`: The `opening` parameter holds moves played before the players take over.
+/// * `time`: The `time` parameter is the thinking time of each player for the whole game, if the game
+/// is timed.
+/// * `rng`: The `rng` parameter is passed on to the players.
+///
+/// Returns:
+///
+/// The record of the game.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) fn play_match(x: &mut dyn Player, o: &mut dyn Player, opening: &[i16], time: Option<Duration>, rng: &mut ChaCha8Rng) -> MatchRecord {
+    let mut board2 = Board::new();
+    board2.change_type();
+    let mut boards = [Board::new(), board2];
+    let mut clocks = [Clock { remaining: time }; 2];
+    let mut moves = Vec::new();
+    for _move in opening {
+        boards.iter_mut().for_each(|board| board.play_move(*_move));
+        moves.push(*_move);
+    }
+
+    let result = loop {
+        let side = moves.len() % 2;
+        let player: &mut dyn Player = if side == 0 { &mut *x } else { &mut *o };
+        let started = Instant::now();
+        let Some(_move) = player.choose_move(&boards[side], &clocks[side], rng) else {
+            break if side == 0 { -1 } else { 1 };
+        };
+        if let Some(remaining) = clocks[side].remaining.as_mut() {
+            *remaining = remaining.saturating_sub(started.elapsed());
+        }
+        boards.iter_mut().for_each(|board| board.play_move(_move));
+        moves.push(_move);
+        if boards[0].has_game_ended() {
+            break if !boards[0].is_a_win() {
+                0
+            } else if side == 0 {
+                1
+            } else {
+                -1
+            };
+        }
+    };
+    let record = MatchRecord { moves, result };
+    x.observe_result(record.x_score());
+    o.observe_result(1.0 - record.x_score());
+    record
+}
+
+/// The function `random_move` returns a legal move of a position, chosen uniformly.
+pub(crate) fn random_move(board: &Board, rng: &mut ChaCha8Rng) -> i16 {
+    *bitboard_functions::get_indi_bits(board.clone().gen_move())
+        .choose(rng)
+        .expect("the game is not over")
+}
+
+impl Player for Minimax {
+    /// Plays the one-ply search of `minimax`.
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, _rng: &mut ChaCha8Rng) -> Option<i16> {
+        Some(self.minimax(board.clone()))
+    }
+}
+
+/// The struct `Search` is a network playing the moves of `Minimax::search`.
+///
+/// Properties:
+///
+/// * `mm`: The `mm` property is the network and its search.
+/// * `limits`: The `limits` property bounds the search of each move; when the game is timed, each move
+/// also gets its share of the clock.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct Search {
+    mm: Minimax,
+    limits: SearchLimits,
+}
+
+impl Search {
+    /// The function `new` creates a searching player.
+    pub fn new(mm: Minimax, limits: SearchLimits) -> Self {
+        Search { mm, limits }
+    }
+}
+
+impl Player for Search {
+    fn choose_move(&mut self, board: &Board, clock: &Clock, _rng: &mut ChaCha8Rng) -> Option<i16> {
+        let time = match (self.limits.time, clock.move_time(board)) {
+            (Some(limit), Some(share)) => Some(limit.min(share)),
+            (limit, share) => limit.or(share),
+        };
+        Some(self.mm.search(board, SearchLimits { time, ..self.limits }).best_move)
+    }
+}
+
+/// The struct `RandomPlayer` plays a legal move chosen uniformly.
+pub(crate) struct RandomPlayer;
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        Some(random_move(board, rng))
+    }
+}
+
+impl Player for Solver {
+    /// Plays one of the best moves, chosen at random.
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        self.best_moves(board).choose(rng).copied()
+    }
+}
+
+impl Player for Mcts {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        Some(self.search(board, rng))
+    }
+}
+
+impl Player for Heuristic {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        Some(self.choose(board, rng).0)
+    }
+}
+
+impl Player for QLearning {
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        let (me, opp) = board.get_sides();
+        Some(self.best_move(me, opp, rng))
+    }
+}
+
+/// The struct `Exploring` makes another player play a random move instead of its own with a given
+/// probability, as the players of training games do.
+///
+/// Properties:
+///
+/// * `player`: The `player` property is the player whose moves are replaced.
+/// * `exploration`: The `exploration` property is the probability of a random move.
+pub(crate) struct Exploring<P> {
+    pub player: P,
+    pub exploration: f64,
+}
+
+impl<P: Player> Player for Exploring<P> {
+    fn choose_move(&mut self, board: &Board, clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        if self.exploration > 0.0 && rng.gen::<f64>() < self.exploration {
+            return Some(random_move(board, rng));
+        }
+        self.player.choose_move(board, clock, rng)
+    }
+
+    fn observe_result(&mut self, score: f64) {
+        self.player.observe_result(score);
+    }
+}
+
+/// The enum `Expert` is the strong player a noisy expert follows.
+///
+/// Variants:
+///
+/// * `Solver`: The perfect player, choosing at random among the best moves.
+/// * `Heuristic`: The rule-based `Heuristic` player at full skill.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Expert {
+    Solver,
+    Heuristic,
+}
+
+impl Expert {
+    /// The function `noisy` builds a noisy expert, a baseline whose strength can be set anywhere
+    /// between the random player and the expert.
+    ///
+    /// Arguments:
+    ///
+    /// * `accuracy`: The `accuracy` parameter is the probability, clamped between 0 and 1, that the
+    /// expert's move is played rather than a random one.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn noisy(self, accuracy: f64) -> Exploring<Box<dyn Player>> {
+        let player: Box<dyn Player> = match self {
+            Expert::Solver => Box::new(Solver::new()),
+            Expert::Heuristic => Box::new(Heuristic::new(1.0)),
+        };
+        Exploring { player, exploration: 1.0 - accuracy.clamp(0.0, 1.0) }
+    }
+}
+
+impl Player for Box<dyn Player> {
+    fn choose_move(&mut self, board: &Board, clock: &Clock, rng: &mut ChaCha8Rng) -> Option<i16> {
+        (**self).choose_move(board, clock, rng)
+    }
+
+    fn observe_result(&mut self, score: f64) {
+        (**self).observe_result(score);
+    }
+}
+
+/// The struct `Human` is the user, playing on the standard input: the board is printed before each
+/// move and the user types the number of a cell, counted row by row from 1 to 9.
+pub(crate) struct Human;
+
+impl Player for Human {
+    /// Asks the user for a move until a free cell is given; the user resigns with `q` or by closing the
+    /// input.
+    fn choose_move(&mut self, board: &Board, _clock: &Clock, _rng: &mut ChaCha8Rng) -> Option<i16> {
+        print_board(board);
+        let (me, opp) = board.get_sides();
+        let stdin = io::stdin();
+        loop {
+            print!("Your move (1-9, q to resign): ");
+            io::stdout().flush().ok()?;
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim();
+            if line.eq_ignore_ascii_case("q") {
+                return None;
+            }
+            match line.parse::<u32>() {
+                Ok(cell @ 1..=9) if (me | opp) & 1 << (cell - 1) == 0 => return Some(1 << (cell - 1)),
+                Ok(cell @ 1..=9) => println!("Cell {} is taken.", cell),
+                _ => println!("'{}' is not a cell: type a number from 1 to 9.", line),
+            }
+        }
+    }
+
+    fn observe_result(&mut self, score: f64) {
+        println!(
+            "{}",
+            if score > 0.5 {
+                "You won!"
+            } else if score < 0.5 {
+                "You lost."
+            } else {
+                "It is a draw."
+            }
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use clap::ValueEnum;
+use neuroflow::FeedForward;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, workspace::Workspace};
+
+/// The enum `Sampling` lists the ways opponents are drawn from the pool.
+///
+/// Variants:
+///
+/// * `Uniform`: Every snapshot is equally likely.
+/// * `Recency`: Newer snapshots are more likely, in proportion to their rank by age.
+/// * `WinRate`: Prioritized fictitious self-play: snapshots the trainee scores badly against are
+/// more likely.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Sampling {
+    Uniform,
+    Recency,
+    WinRate,
+}
+
+/// The struct `Snapshot` describes a frozen copy of the trainee kept in the pool.
+///
+/// Properties:
+///
+/// * `id`: The `id` property names the snapshot and its file; ids are never reused.
+/// * `iteration`: The `iteration` property is the training iteration the snapshot was taken at.
+/// * `games`: The `games` property is the number of training games the trainee played against it.
+/// * `score`: The `score` property is the trainee's total score in those games, counting a win as 1
+/// and a draw as 0.5.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub id: u32,
+    pub iteration: i16,
+    pub games: u32,
+    pub score: f64,
+}
+
+impl Snapshot {
+    /// The function `win_rate` returns the trainee's expected score against the snapshot, shrunk
+    /// towards one half while few games have been played.
+    pub fn win_rate(&self) -> f64 {
+        (self.score + 1.0) / (self.games as f64 + 2.0)
+    }
+}
+
+/// The struct `OpponentPool` is the league of past trainees the trainee is trained against. The
+/// networks live in the pool directory of the workspace and the metadata in its `pool.toml`.
+///
+/// Properties:
+///
+/// * `capacity`: The `capacity` property is the maximum number of snapshots kept; the oldest one is
+/// dropped when a new snapshot would exceed it.
+/// * `next_id`: The `next_id` property is the id given to the next snapshot.
+/// * `snapshots`: The `snapshots` property lists the snapshots from oldest to newest.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct OpponentPool {
+    capacity: usize,
+    next_id: u32,
+    snapshots: Vec<Snapshot>,
+}
+
+impl OpponentPool {
+    /// The function `new` creates an empty pool.
+    ///
+    /// Arguments:
+    ///
+    /// * `capacity`: The `capacity` parameter is the maximum number of snapshots kept, at least one.
+    pub fn new(capacity: usize) -> Self {
+        OpponentPool {
+            capacity: capacity.max(1),
+            next_id: 0,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// The function `set_capacity` changes the maximum number of snapshots kept, evicting the oldest
+    /// ones if the pool holds more. As in `add`, their files are left for `Workspace::save_state`.
+    ///
+    /// Arguments:
+    ///
+    /// * `capacity`: The `capacity` parameter is the new maximum number of snapshots, at least one.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        let evicted = self.snapshots.len().saturating_sub(self.capacity);
+        self.snapshots.drain(..evicted);
+    }
+
+    /// The function `snapshots` lists the snapshots of the pool from oldest to newest.
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// The function `add` stores a copy of the trainee in the pool, evicting the oldest snapshot if
+    /// the pool is full. The file of an evicted snapshot is left in place until a training state
+    /// without it has been saved, as `Workspace::save_state` then removes it.
+    ///
+    /// Arguments:
+    ///
+    /// * `workspace`: The `workspace` parameter is the run directory the snapshot is written to.
+    /// * `nn`: The `nn` parameter is the trainee to be copied.
+    /// * `iteration`: The `iteration` parameter is the current training iteration.
+    ///
+    /// Returns:
+    ///
+    /// The id of the new snapshot, or the error raised while writing its file.
+    pub fn add(&mut self, workspace: &Workspace, nn: &mut FeedForward, iteration: i16) -> Result<u32> {
+        let id = self.next_id;
+        workspace.save(nn, &workspace.opponent_path(id))?;
+        self.next_id += 1;
+        self.snapshots.push(Snapshot { id, iteration, games: 0, score: 0.0 });
+        self.set_capacity(self.capacity);
+        Ok(id)
+    }
+
+    /// The function `sample` draws opponents from the pool, with replacement.
+    ///
+    /// Arguments:
+    ///
+    /// * `sampling`: The `sampling` parameter is the rule the opponents are drawn by.
+    /// * `count`: The `count` parameter is the number of opponents to draw.
+    /// * `rng`: The `rng` parameter is the random generator used for drawing.
+    ///
+    /// Returns:
+    ///
+    /// The ids of the drawn snapshots, or an empty vector if the pool is empty.
+    pub fn sample(&self, sampling: Sampling, count: usize, rng: &mut impl Rng) -> Vec<u32> {
+        if self.snapshots.is_empty() {
+            return Vec::new();
+        }
+        let weights: Vec<f64> = self
+            .snapshots
+            .iter()
+            .enumerate()
+            .map(|(rank, snapshot)| match sampling {
+                Sampling::Uniform => 1.0,
+                Sampling::Recency => (rank + 1) as f64,
+                Sampling::WinRate => (1.0 - snapshot.win_rate()).powi(2),
+            })
+            .collect();
+        let distribution = WeightedIndex::new(&weights).expect("pool weights are positive");
+        (0..count)
+            .map(|_| self.snapshots[distribution.sample(rng)].id)
+            .collect()
+    }
+
+    /// The function `record` adds the result of a training game to the statistics of a snapshot.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The `id` parameter is the snapshot the trainee played against.
+    /// * `score`: The `score` parameter is the trainee's score in the game: 1, 0.5 or 0.
+    pub fn record(&mut self, id: u32, score: f64) {
+        if let Some(snapshot) = self.snapshots.iter_mut().find(|snapshot| snapshot.id == id) {
+            snapshot.games += 1;
+            snapshot.score += score;
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{bitboard_functions, board::Board};
+
+/// The probability of a random move while learning.
+const EPSILON: f64 = 0.2;
+/// The step size of the updates.
+const ALPHA: f64 = 0.3;
+
+/// The struct `QLearning` is a tabular Q-learning player: it holds a value for every move of every
+/// position it met while learning, learnt by playing against itself.
+///
+/// Properties:
+///
+/// * `values`: The `values` property maps each position, as the stones of the side to move and of the
+/// other side, to the value of each of its cells for the side to move, from -1 for a loss to 1 for a
+/// win.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone)]
+pub(crate) struct QLearning {
+    values: HashMap<(i16, i16), [f64; 9]>,
+}
+
+impl QLearning {
+    /// The function `train` learns a table by playing games against itself. Each move is the best one
+    /// of the table, or a random one with probability `EPSILON`, and its value is moved towards the
+    /// result of the game if the move ends it, or towards the negated value of the opponent's best
+    /// reply otherwise.
+    ///
+    /// Arguments:
+    ///
+    /// * `episodes`: The `episodes` parameter is the number of games played.
+    /// * `rng`: The `rng` parameter draws the random moves and breaks ties.
+    pub fn train(episodes: u32, rng: &mut impl Rng) -> Self {
+        let mut learner = QLearning { values: HashMap::new() };
+        for _ in 0..episodes {
+            let (mut me, mut opp) = (0, 0);
+            loop {
+                let _move = if rng.gen_bool(EPSILON) {
+                    *bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD).choose(rng).unwrap_or(&0)
+                } else {
+                    learner.best_move(me, opp, rng)
+                };
+                let next = me | _move;
+                let over = Board::has_line(next) || (next | opp) & Board::BOARD == Board::BOARD;
+                let target = if Board::has_line(next) {
+                    1.0
+                } else if over {
+                    0.0
+                } else {
+                    -learner.best_value(opp, next)
+                };
+                let value = &mut learner.values.entry((me, opp)).or_insert([0.0; 9])[_move.trailing_zeros() as usize];
+                *value += ALPHA * (target - *value);
+                if over {
+                    break;
+                }
+                (me, opp) = (opp, next);
+            }
+        }
+        learner
+    }
+
+    /// The function `best_move` returns the empty cell with the highest value, choosing at random
+    /// among equal ones.
+    ///
+    /// Arguments:
+    ///
+    /// * `me`: The `me` parameter is the bitboard of the side to move.
+    /// * `opp`: The `opp` parameter is the bitboard of the other side.
+    /// * `rng`: The `rng` parameter breaks ties.
+    pub fn best_move(&self, me: i16, opp: i16, rng: &mut impl Rng) -> i16 {
+        let moves = bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD);
+        let best = self.best_value(me, opp);
+        let values = self.values.get(&(me, opp));
+        let best_moves: Vec<i16> = moves
+            .into_iter()
+            .filter(|_move| values.map_or(0.0, |values| values[_move.trailing_zeros() as usize]) >= best)
+            .collect();
+        *best_moves.choose(rng).unwrap_or(&0)
+    }
+
+    /// The function `best_value` returns the highest value of the empty cells of a position, 0 for a
+    /// position the table has not met.
+    fn best_value(&self, me: i16, opp: i16) -> f64 {
+        let Some(values) = self.values.get(&(me, opp)) else {
+            return 0.0;
+        };
+        bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD)
+            .into_iter()
+            .map(|_move| values[_move.trailing_zeros() as usize])
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The rating given to a player the first time it plays.
+pub const INITIAL_RATING: f64 = 1500.0;
+/// The largest change of rating a single game can cause.
+const K_FACTOR: f64 = 32.0;
+/// The rating name of the model being trained.
+pub const TRAINEE: &str = "trainee";
+/// The rating name of the person at the keyboard.
+pub const HUMAN: &str = "human";
+
+/// The struct `Rating` is the Elo rating of a player together with its game record.
+///
+/// Properties:
+///
+/// * `elo`: The `elo` property is the current Elo rating.
+/// * `wins`: The `wins` property is the number of rated games won.
+/// * `draws`: The `draws` property is the number of rated games drawn.
+/// * `losses`: The `losses` property is the number of rated games lost.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Rating {
+    pub elo: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            elo: INITIAL_RATING,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        }
+    }
+}
+
+impl Rating {
+    /// The function `games` returns the number of rated games played.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The function `add_result` adds a game to the record, without touching the rating.
+    fn add_result(&mut self, score: f64) {
+        if score > 0.5 {
+            self.wins += 1;
+        } else if score < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+}
+
+/// The struct `Ratings` keeps the Elo ratings of named players, such as `trainee`, `snapshot-3`,
+/// `solver`, `random` or `human`. It is persisted in the `ratings.toml` file of the workspace.
+///
+/// Properties:
+///
+/// * `players`: The `players` property maps each player name to its rating.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Ratings {
+    players: BTreeMap<String, Rating>,
+}
+
+impl Ratings {
+    /// The function `get` returns the rating of a player, or a fresh rating if it never played.
+    pub fn get(&self, name: &str) -> Rating {
+        self.players.get(name).cloned().unwrap_or_default()
+    }
+
+    /// The function `expected_score` returns the score a player rated `rating` is expected to make
+    /// against a player rated `opponent`.
+    pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+    }
+
+    /// The function `record` updates the ratings of two players after a game between them.
+    ///
+    /// Arguments:
+    ///
+    /// * `player`: The `player` parameter is the name of the first player.
+    /// * `opponent`: The `opponent` parameter is the name of the second player.
+    /// * `score`: The `score` parameter is the score of the first player: 1 for a win, 0.5 for a draw
+    /// and 0 for a loss.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn record(&mut self, player: &str, opponent: &str, score: f64) {
+        let a = self.get(player);
+        let b = self.get(opponent);
+        let change = K_FACTOR * (score - Self::expected_score(a.elo, b.elo));
+
+        let a = self.players.entry(player.to_string()).or_default();
+        a.elo += change;
+        a.add_result(score);
+        let b = self.players.entry(opponent.to_string()).or_default();
+        b.elo -= change;
+        b.add_result(1.0 - score);
+    }
+
+    /// The function `leaderboard` lists the rated players from the highest rating to the lowest.
+    pub fn leaderboard(&self) -> Vec<(&str, &Rating)> {
+        let mut board: Vec<(&str, &Rating)> = self.players.iter().map(|(name, rating)| (name.as_str(), rating)).collect();
+        board.sort_by(|a, b| b.1.elo.total_cmp(&a.1.elo));
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_game_moves_both_ratings_by_the_same_amount() {
+        let mut ratings = Ratings::default();
+        ratings.record("a", "b", 1.0);
+        ratings.record("a", "c", 0.5);
+        ratings.record("c", "b", 0.0);
+        let total: f64 = ratings.leaderboard().iter().map(|(_, rating)| rating.elo).sum();
+        assert!((total - 3.0 * INITIAL_RATING).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_update_does_not_depend_on_the_order_of_the_players() {
+        let mut forward = Ratings::default();
+        let mut backward = Ratings::default();
+        for (a, b, score) in [("a", "b", 1.0), ("b", "a", 0.5), ("a", "b", 0.0), ("b", "a", 1.0)] {
+            forward.record(a, b, score);
+            backward.record(b, a, 1.0 - score);
+        }
+        for name in ["a", "b"] {
+            let (forward, backward) = (forward.get(name), backward.get(name));
+            assert!((forward.elo - backward.elo).abs() < 1e-9);
+            assert_eq!((forward.wins, forward.draws, forward.losses), (backward.wins, backward.draws, backward.losses));
+        }
+    }
+
+    #[test]
+    fn a_draw_between_equal_players_changes_no_rating() {
+        let mut ratings = Ratings::default();
+        ratings.record("a", "b", 0.5);
+        assert_eq!(ratings.get("a").elo, INITIAL_RATING);
+        assert_eq!(ratings.get("b").elo, INITIAL_RATING);
+        assert_eq!(ratings.get("a").draws, 1);
+        assert!((Ratings::expected_score(1700.0, 1500.0) + Ratings::expected_score(1500.0, 1700.0) - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{bitboard_functions, board::Board};
+
+/// The struct `Solver` computes the game-theoretic value of tic-tac-toe positions by exhaustive
+/// negamax search, remembering every position it has already solved.
+///
+/// Properties:
+///
+/// * `table`: The `table` property caches the value of each solved position for the side to move,
+/// indexed by the bitboard of the side to move followed by the bitboard of the other side.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct Solver {
+    table: Vec<i8>,
+}
+
+impl Solver {
+    const UNKNOWN: i8 = i8::MIN;
+
+    /// The function `new` creates a solver with an empty transposition table.
+    pub fn new() -> Self {
+        Solver {
+            table: vec![Self::UNKNOWN; 1 << 18],
+        }
+    }
+
+    /// The function `solve` returns the value of a position under perfect play.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position to solve.
+    ///
+    /// Returns:
+    ///
+    /// `1` if the side to move wins, `0` if the game is drawn and `-1` if the side to move loses.
+    pub fn solve(&mut self, board: &Board) -> i8 {
+        let (me, opp) = board.get_sides();
+        self.negamax(me, opp)
+    }
+
+    /// The function `score_moves` solves every legal move of a position.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position whose moves are scored.
+    ///
+    /// Returns:
+    ///
+    /// A vector pairing each legal move with its value for the side to move, using the same scale as
+    /// `solve`.
+    pub fn score_moves(&mut self, board: &Board) -> Vec<(i16, i8)> {
+        let (me, opp) = board.get_sides();
+        if Board::has_line(opp) {
+            return Vec::new();
+        }
+        bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD)
+            .into_iter()
+            .map(|_move| (_move, -self.negamax(opp, me | _move)))
+            .collect()
+    }
+
+    /// The function `best_moves` returns every move that keeps the value of the position.
+    ///
+    /// Arguments:
+    ///
+    /// * `board`: The `board` parameter is the position to be played.
+    pub fn best_moves(&mut self, board: &Board) -> Vec<i16> {
+        let scores = self.score_moves(board);
+        let best = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+        scores
+            .into_iter()
+            .filter(|(_, score)| *score == best)
+            .map(|(_move, _)| _move)
+            .collect()
+    }
+
+    /// The function `negamax` solves the position where `me` is to move against `opp`.
+    fn negamax(&mut self, me: i16, opp: i16) -> i8 {
+        let index = ((me as usize) << 9) | opp as usize;
+        if self.table[index] != Self::UNKNOWN {
+            return self.table[index];
+        }
+        let value = if Board::has_line(opp) {
+            -1
+        } else if me | opp == Board::BOARD {
+            0
+        } else {
+            let mut best = -1;
+            for _move in bitboard_functions::get_indi_bits(!(me | opp) & Board::BOARD) {
+                best = best.max(-self.negamax(opp, me | _move));
+                if best == 1 {
+                    break;
+                }
+            }
+            best
+        };
+        self.table[index] = value;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(notation: &str) -> i8 {
+        Solver::new().solve(&Board::from_notation(notation).unwrap())
+    }
+
+    #[test]
+    fn the_empty_board_is_a_draw() {
+        assert_eq!(solve("........."), 0);
+    }
+
+    #[test]
+    fn solves_known_positions() {
+        // X completes the top row.
+        assert_eq!(solve("XX.OO...."), 1);
+        // O cannot block both the top row and the left column.
+        assert_eq!(solve("XX.X.O.O."), -1);
+        // Taking the center against a corner holds the draw.
+        assert_eq!(solve("X...O...."), 0);
+        // The game is over: O made a line and X, to move, has lost.
+        assert_eq!(solve("XX.OOO.X."), -1);
+    }
+
+    #[test]
+    fn scores_every_move() {
+        let mut solver = Solver::new();
+        let board = Board::from_notation("XX.OO....").unwrap();
+        let scores = solver.score_moves(&board);
+        assert_eq!(scores.len(), 5);
+        // Cell 3 wins, cell 6 blocks O and every other move lets O complete the middle row.
+        for (_move, score) in scores {
+            let expected = match bitboard_functions::to_cell(_move) {
+                3 => 1,
+                6 => 0,
+                _ => -1,
+            };
+            assert_eq!(score, expected, "cell {}", bitboard_functions::to_cell(_move));
+        }
+        assert_eq!(solver.best_moves(&board), vec![1 << 2]);
+    }
+}
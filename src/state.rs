@@ -0,0 +1,72 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::TrainerConfig, curriculum::Curriculum, pool::OpponentPool};
+
+/// The struct `TrainerState` is everything besides the networks that a `Trainer` needs to continue an
+/// interrupted run exactly where it stopped. It is written to the workspace after every iteration.
+///
+/// Properties:
+///
+/// * `iteration`: The `iteration` property is the number of the next iteration to run.
+/// * `config`: The `config` property is the configuration the run was started with.
+/// * `rng`: The `rng` property is the state of the random generator used to sample training data.
+/// * `curriculum`: The `curriculum` property is the progress of the run through its curriculum.
+/// * `pool`: The `pool` property is the opponent pool and the trainee's score against each snapshot,
+/// saved with the rest of the state so that both always describe the same iteration.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct TrainerState {
+    pub iteration: i16,
+    pub config: TrainerConfig,
+    pub rng: RngState,
+    #[serde(default)]
+    pub curriculum: Curriculum,
+    pub pool: OpponentPool,
+}
+
+/// The struct `RngState` is the position of a `ChaCha8Rng` in its stream. The numbers are kept as
+/// hexadecimal strings because TOML integers cannot hold them.
+///
+/// Properties:
+///
+/// * `seed`: The `seed` property is the 32 byte key of the generator.
+/// * `stream`: The `stream` property is the stream number of the generator.
+/// * `word_pos`: The `word_pos` property is the number of 32 bit words already drawn.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RngState {
+    seed: String,
+    stream: String,
+    word_pos: String,
+}
+
+impl RngState {
+    /// The function `capture` records the current position of a generator.
+    pub fn capture(rng: &ChaCha8Rng) -> Self {
+        RngState {
+            seed: rng.get_seed().iter().map(|byte| format!("{:02x}", byte)).collect(),
+            stream: format!("{:x}", rng.get_stream()),
+            word_pos: format!("{:x}", rng.get_word_pos()),
+        }
+    }
+
+    /// The function `restore` rebuilds the generator recorded by `capture`.
+    ///
+    /// Returns:
+    ///
+    /// The generator, positioned where it was captured, or `None` if a field is not valid hexadecimal.
+    pub fn restore(&self) -> Option<ChaCha8Rng> {
+        if self.seed.len() != 64 {
+            return None;
+        }
+        let mut seed = [0u8; 32];
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(self.seed.get(2 * i..2 * i + 2)?, 16).ok()?;
+        }
+        let mut rng = ChaCha8Rng::from_seed(seed);
+        rng.set_stream(u64::from_str_radix(&self.stream, 16).ok()?);
+        rng.set_word_pos(u128::from_str_radix(&self.word_pos, 16).ok()?);
+        Some(rng)
+    }
+}
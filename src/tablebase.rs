@@ -0,0 +1,463 @@
+use std::{fmt, fs, path::Path};
+
+use crate::error::{Error, Result};
+
+/// The longest side of the boards a table can be built for: every position of a 6x6 board still has
+/// an index that fits in 64 bits.
+pub(crate) const MAX_SIDE: u8 = 6;
+/// The bytes opening a tablebase file.
+const MAGIC: &[u8; 4] = b"ACTB";
+/// The length of the header of a tablebase file: the bytes `ACTB`, the rows, the columns, the stones
+/// in a row that win and the largest number of empty cells.
+const HEADER: usize = MAGIC.len() + 4;
+
+/// The struct `Shape` is the m,n,k game a table is built for.
+///
+/// Properties:
+///
+/// * `rows`: The `rows` property is the number of rows of the board.
+/// * `cols`: The `cols` property is the number of columns of the board.
+/// * `k`: The `k` property is the number of stones in a row, column or diagonal that win.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Shape {
+    pub rows: u8,
+    pub cols: u8,
+    pub k: u8,
+}
+
+impl Shape {
+    /// The 3x3 board with three in a row, the game the agents play.
+    pub const TIC_TAC_TOE: Shape = Shape { rows: 3, cols: 3, k: 3 };
+
+    /// The function `new` checks the dimensions of a game.
+    ///
+    /// Returns:
+    ///
+    /// The shape, or `None` if a side is longer than `MAX_SIDE` or no line of `k` stones fits on the
+    /// board.
+    pub fn new(rows: u8, cols: u8, k: u8) -> Option<Self> {
+        let fits = (1..=MAX_SIDE).contains(&rows) && (1..=MAX_SIDE).contains(&cols) && (1..=rows.max(cols)).contains(&k);
+        fits.then_some(Shape { rows, cols, k })
+    }
+
+    /// The function `cells` returns the number of cells of the board.
+    pub fn cells(self) -> u32 {
+        self.rows as u32 * self.cols as u32
+    }
+
+    /// The function `board` returns the bitboard of every cell, numbered row by row.
+    fn board(self) -> u64 {
+        (1 << self.cells()) - 1
+    }
+
+    /// The function `lines` returns the bitboards of the rows, columns and diagonals of `k` cells.
+    fn lines(self) -> Vec<u64> {
+        let (rows, cols, k) = (self.rows as i32, self.cols as i32, self.k as i32);
+        let mut lines = Vec::new();
+        for row in 0..rows {
+            for col in 0..cols {
+                for (down, right) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                    if row + down * (k - 1) < rows && (0..cols).contains(&(col + right * (k - 1))) {
+                        lines.push((0..k).fold(0, |line, i| line | 1 << ((row + down * i) * cols + col + right * i)));
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} board with {} in a row", self.rows, self.cols, self.k)
+    }
+}
+
+/// The struct `Tablebase` holds the exact result of every position of an m,n,k game with few empty
+/// cells, for the search to use at its leaves instead of the network. Each result takes two bits, at
+/// the place given by `index`: 0 when the position cannot occur in a game, then 1, 2 and 3 for a loss,
+/// a draw and a win of the side to move.
+///
+/// Properties:
+///
+/// * `shape`: The `shape` property is the game the table is built for.
+/// * `max_empty`: The `max_empty` property is the largest number of empty cells of the positions in
+/// the table.
+/// * `lines`: The `lines` property holds the bitboards of the lines of the game.
+/// * `offsets`: The `offsets` property holds, for each number of empty cells, the index of the first
+/// position with that many.
+/// * `results`: The `results` property packs four results per byte.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug)]
+pub(crate) struct Tablebase {
+    shape: Shape,
+    max_empty: u8,
+    lines: Vec<u64>,
+    offsets: Vec<usize>,
+    results: Vec<u8>,
+}
+
+impl Tablebase {
+    /// The function `generate` solves every legal position of a game with at most `max_empty` empty
+    /// cells. The positions are solved backwards, by number of empty cells, so that every move of a
+    /// position leads to a position solved before it.
+    ///
+    /// Arguments:
+    ///
+    /// * `shape`: The `shape` parameter is the game to be solved.
+    /// * `max_empty`: The `max_empty` parameter is the largest number of empty cells solved; it is
+    /// capped at the number of cells of the board.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn generate(shape: Shape, max_empty: u8) -> Self {
+        let mut table = Self::empty(shape, max_empty.min(shape.cells() as u8));
+        for empty in 0..=table.max_empty as usize {
+            for index in table.offsets[empty]..table.offsets[empty + 1] {
+                let (me, opp) = table.position(empty as u32, index - table.offsets[empty]);
+                if let Some(result) = table.solve(me, opp) {
+                    table.set(index, result);
+                }
+            }
+        }
+        table
+    }
+
+    /// The function `probe` looks a position up.
+    ///
+    /// Arguments:
+    ///
+    /// * `me`: The `me` parameter is the bitboard of the side to move, with the cells numbered row by
+    /// row.
+    /// * `opp`: The `opp` parameter is the bitboard of the other side.
+    ///
+    /// Returns:
+    ///
+    /// The result for the side to move, 1 for a win, 0 for a draw and -1 for a loss, or `None` if the
+    /// position is not in the table.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn probe(&self, me: u64, opp: u64) -> Option<i8> {
+        if (!(me | opp) & self.shape.board()).count_ones() > self.max_empty as u32 {
+            return None;
+        }
+        let index = self.index(me, opp)?;
+        match (self.results[index / 4] >> (2 * (index % 4))) & 0b11 {
+            0 => None,
+            code => Some(code as i8 - 2),
+        }
+    }
+
+    /// The function `shape` returns the game the table is built for.
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The function `max_empty` returns the largest number of empty cells of the positions in the
+    /// table.
+    pub fn max_empty(&self) -> u8 {
+        self.max_empty
+    }
+
+    /// The function `len` returns the number of positions in the table.
+    pub fn len(&self) -> usize {
+        self.results.iter().map(|byte| (0..4).filter(|slot| (byte >> (2 * slot)) & 0b11 != 0).count()).sum()
+    }
+
+    /// The function `load` reads a table written by `save`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be read.
+    ///
+    /// Returns:
+    ///
+    /// The table, or an `Error::Tablebase` if the file is not one.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+        let invalid = |message: &str| Error::Tablebase { path: path.to_path_buf(), message: message.to_string() };
+        let (header, results) = bytes.split_at(bytes.len().min(HEADER));
+        if header.len() != HEADER || &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a tablebase file"));
+        }
+        let [rows, cols, k, max_empty] = [header[4], header[5], header[6], header[7]];
+        let shape = Shape::new(rows, cols, k).ok_or_else(|| invalid("the table is for a board no table can be built for"))?;
+        if max_empty as u32 > shape.cells() {
+            return Err(invalid("the table has more empty cells than the board"));
+        }
+        let mut table = Self::empty(shape, max_empty);
+        if results.len() != table.results.len() {
+            return Err(invalid("the table has the wrong size"));
+        }
+        table.results.copy_from_slice(results);
+        Ok(table)
+    }
+
+    /// The function `load_for_game` reads a table written by `save` for the 3x3 board the agents play,
+    /// the only table their searches can probe.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be read.
+    ///
+    /// Returns:
+    ///
+    /// The table, or an `Error::Tablebase` if the file is not one or is built for another game.
+    pub fn load_for_game(path: &Path) -> Result<Self> {
+        let table = Self::load(path)?;
+        if table.shape != Shape::TIC_TAC_TOE {
+            return Err(Error::Tablebase {
+                path: path.to_path_buf(),
+                message: format!("the table is for the {}, not the {} of the agents", table.shape, Shape::TIC_TAC_TOE),
+            });
+        }
+        Ok(table)
+    }
+
+    /// The function `save` writes the table: the bytes `ACTB`, the rows, the columns, the stones in a
+    /// row that win and the largest number of empty cells, one byte each, then the packed results.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[self.shape.rows, self.shape.cols, self.shape.k, self.max_empty]);
+        bytes.extend_from_slice(&self.results);
+        fs::write(path, bytes).map_err(|e| Error::io(path, e))
+    }
+
+    /// The function `empty` returns a table of the given game and number of empty cells with no
+    /// position solved yet.
+    fn empty(shape: Shape, max_empty: u8) -> Self {
+        let cells = shape.cells();
+        let offsets: Vec<usize> = (0..=cells + 1)
+            .scan(0, |first, empty| {
+                let offset = *first;
+                if empty <= cells {
+                    let stones = cells - empty;
+                    *first += Self::choose(cells, stones) * Self::choose(stones, stones / 2);
+                }
+                Some(offset)
+            })
+            .collect();
+        let results = vec![0; offsets[max_empty as usize + 1].div_ceil(4)];
+        Tablebase { shape, max_empty, lines: shape.lines(), offsets, results }
+    }
+
+    /// The function `has_line` checks whether a side has `k` stones in a row.
+    fn has_line(&self, stones: u64) -> bool {
+        self.lines.iter().any(|line| stones & line == *line)
+    }
+
+    /// The function `solve` finds the result of a position from the results of the positions its
+    /// moves lead to.
+    ///
+    /// Returns:
+    ///
+    /// The result for the side to move, or `None` if the position cannot occur in a game.
+    fn solve(&self, me: u64, opp: u64) -> Option<i8> {
+        let behind = opp.count_ones() as i32 - me.count_ones() as i32;
+        if !(0..=1).contains(&behind) || self.has_line(me) {
+            return None;
+        }
+        if self.has_line(opp) {
+            return Some(-1);
+        }
+        let empty = !(me | opp) & self.shape.board();
+        if empty == 0 {
+            return Some(0);
+        }
+        (0..self.shape.cells())
+            .filter(|cell| empty & 1 << cell != 0)
+            .map(|cell| self.probe(opp, me | 1 << cell).map(|result| -result))
+            .try_fold(-1, |best, result| Some(best.max(result?)))
+    }
+
+    /// The function `set` stores the result of the position with the given index.
+    fn set(&mut self, index: usize, result: i8) {
+        let shift = 2 * (index % 4);
+        self.results[index / 4] = (self.results[index / 4] & !(0b11 << shift)) | (((result + 2) as u8) << shift);
+    }
+
+    /// The function `index` is the perfect hash of the table. The positions are grouped by number of
+    /// empty cells, fewest first; within a group a position is numbered by the rank of its occupied
+    /// cells among the sets of that many cells, then by the rank of the stones of the side to move
+    /// among the occupied cells. Only positions with the stone counts of a game are numbered: the side
+    /// to move has half of the stones, rounded down.
+    ///
+    /// Returns:
+    ///
+    /// The index of the position, or `None` if its stone counts cannot occur in a game.
+    fn index(&self, me: u64, opp: u64) -> Option<usize> {
+        let occupied = (me | opp) & self.shape.board();
+        let stones = occupied.count_ones();
+        if me & opp != 0 || me.count_ones() != stones / 2 {
+            return None;
+        }
+        // The stones of the side to move, as a set of places among the occupied cells.
+        let (mine, _) = (0..self.shape.cells())
+            .filter(|cell| occupied & 1 << cell != 0)
+            .fold((0, 0), |(mine, place), cell| (if me & 1 << cell != 0 { mine | 1 << place } else { mine }, place + 1));
+        let rank = Self::rank(occupied) * Self::choose(stones, stones / 2) + Self::rank(mine);
+        Some(self.offsets[(self.shape.cells() - stones) as usize] + rank)
+    }
+
+    /// The function `position` returns the position with `empty` empty cells and the given rank
+    /// among them, the inverse of `index`.
+    fn position(&self, empty: u32, rank: usize) -> (u64, u64) {
+        let cells = self.shape.cells();
+        let stones = cells - empty;
+        let arrangements = Self::choose(stones, stones / 2);
+        let occupied = Self::unrank(cells, stones, rank / arrangements);
+        let mine = Self::unrank(stones, stones / 2, rank % arrangements);
+        let (mut me, mut opp) = (0, 0);
+        for (place, cell) in (0..cells).filter(|cell| occupied & 1 << cell != 0).enumerate() {
+            if mine & 1 << place != 0 {
+                me |= 1 << cell;
+            } else {
+                opp |= 1 << cell;
+            }
+        }
+        (me, opp)
+    }
+
+    /// The function `rank` returns the rank of a set of cells among the sets of as many cells, in
+    /// colexicographic order.
+    fn rank(set: u64) -> usize {
+        (0..u64::BITS)
+            .filter(|cell| set & 1 << cell != 0)
+            .enumerate()
+            .map(|(i, cell)| Self::choose(cell, i as u32 + 1))
+            .sum()
+    }
+
+    /// The function `unrank` returns the set of `size` cells among the first `cells` with the given
+    /// rank, the inverse of `rank`.
+    fn unrank(cells: u32, size: u32, mut rank: usize) -> u64 {
+        let mut set = 0;
+        let mut cell = cells;
+        for i in (1..=size).rev() {
+            cell -= 1;
+            while Self::choose(cell, i) > rank {
+                cell -= 1;
+            }
+            rank -= Self::choose(cell, i);
+            set |= 1 << cell;
+        }
+        set
+    }
+
+    /// The function `choose` returns the binomial coefficient of `n` and `k`.
+    fn choose(n: u32, k: u32) -> usize {
+        if k > n {
+            return 0;
+        }
+        (0..k as usize).fold(1, |product, i| product * (n as usize - i) / (i + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::{board::Board, solver::Solver};
+
+    /// The function `negamax` solves a position of any game by searching it to the end.
+    fn negamax(table: &Tablebase, me: u64, opp: u64) -> i8 {
+        if table.has_line(opp) {
+            return -1;
+        }
+        let empty = !(me | opp) & table.shape.board();
+        (0..table.shape.cells())
+            .filter(|cell| empty & 1 << cell != 0)
+            .map(|cell| -negamax(table, opp, me | 1 << cell))
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn index_and_position_are_inverse() {
+        for shape in [Shape::TIC_TAC_TOE, Shape::new(2, 4, 2).unwrap()] {
+            let table = Tablebase::empty(shape, shape.cells() as u8);
+            for empty in 0..=shape.cells() {
+                for index in table.offsets[empty as usize]..table.offsets[empty as usize + 1] {
+                    let (me, opp) = table.position(empty, index - table.offsets[empty as usize]);
+                    assert_eq!((!(me | opp) & shape.board()).count_ones(), empty);
+                    assert_eq!(table.index(me, opp), Some(index));
+                }
+            }
+            // The side to move never has more stones than the other side.
+            assert_eq!(table.index(0b1, 0), None);
+        }
+    }
+
+    #[test]
+    fn lines_of_tic_tac_toe_are_those_of_the_board() {
+        let mut lines = Shape::TIC_TAC_TOE.lines();
+        let mut patterns: Vec<u64> = Board::WIN_PATTERNS.iter().map(|pattern| *pattern as u64).collect();
+        lines.sort();
+        patterns.sort();
+        assert_eq!(lines, patterns);
+        assert_eq!(Shape::new(4, 5, 4).unwrap().lines().len(), 4 * 2 + 5 + 2 * 2);
+        assert_eq!(Shape::new(3, 3, 4), None);
+        assert_eq!(Shape::new(7, 7, 4), None);
+    }
+
+    #[test]
+    fn probe_agrees_with_the_solver() {
+        let table = Tablebase::generate(Shape::TIC_TAC_TOE, 9);
+        assert_eq!(table.len(), 5478);
+        let mut solver = Solver::new();
+        for empty in 0..=9 {
+            for rank in 0..table.offsets[empty as usize + 1] - table.offsets[empty as usize] {
+                let (me, opp) = table.position(empty, rank);
+                let board = Board::from_sides(me as i16, opp as i16);
+                match table.probe(me, opp) {
+                    Some(result) => assert_eq!(result, solver.solve(&board), "{}", board.to_notation()),
+                    // Only positions the side to move has already won cannot occur in a game.
+                    None => assert!(Board::has_line(me as i16), "{}", board.to_notation()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn probe_agrees_with_a_full_search_on_a_larger_board() {
+        let table = Tablebase::generate(Shape::new(3, 4, 3).unwrap(), 4);
+        for empty in 0..=4 {
+            for rank in 0..table.offsets[empty as usize + 1] - table.offsets[empty as usize] {
+                let (me, opp) = table.position(empty, rank);
+                match table.probe(me, opp) {
+                    Some(result) => assert_eq!(result, negamax(&table, me, opp), "{:012b} {:012b}", me, opp),
+                    // Only positions the side to move has already won cannot occur in a game.
+                    None => assert!(table.has_line(me), "{:012b} {:012b}", me, opp),
+                }
+            }
+        }
+        assert_eq!(table.probe(0, 0), None);
+    }
+
+    #[test]
+    fn a_smaller_table_only_holds_its_layers() {
+        let table = Tablebase::generate(Shape::TIC_TAC_TOE, 3);
+        assert_eq!(table.probe(0, 0), None);
+        let board = Board::from_notation("XOXXOO.X.").unwrap();
+        let (me, opp) = board.get_sides();
+        assert_eq!(table.probe(me as u64, opp as u64), Some(Solver::new().solve(&board)));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let shape = Shape::new(4, 3, 3).unwrap();
+        let table = Tablebase::generate(shape, 5);
+        let path = env::temp_dir().join(format!("acordion-tablebase-test-{}.bin", process::id()));
+        table.save(&path).unwrap();
+        let loaded = Tablebase::load(&path);
+        let for_game = Tablebase::load_for_game(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.shape(), shape);
+        assert_eq!(loaded.max_empty(), 5);
+        assert_eq!(loaded.results, table.results);
+        assert!(matches!(for_game, Err(Error::Tablebase { .. })));
+    }
+}
@@ -0,0 +1,464 @@
+use std::{fmt, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use neuroflow::FeedForward;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+use crate::{
+    board::Board,
+    book::{Booked, OpeningBook},
+    error::Result,
+    heuristic::Heuristic,
+    mcts::Mcts,
+    minimax::{Minimax, SearchLimits},
+    player::{self, random_move, Expert, Player, RandomPlayer, Search},
+    qlearning::QLearning,
+    ratings::{self, Ratings},
+    solver::Solver,
+    workspace::Workspace,
+};
+
+/// The number of playouts per move of `mcts` when none is given.
+const DEFAULT_SIMULATIONS: u32 = 1000;
+/// The accuracy of `noisy` when none is given.
+const DEFAULT_ACCURACY: f64 = 0.5;
+/// The number of self-play games `qlearning` learns from when none is given.
+const DEFAULT_EPISODES: u32 = 50_000;
+
+/// The enum `Network` is a network a tournament entrant plays with.
+///
+/// Variants:
+///
+/// * `Trainee`: `trainee`, the trainee of the workspace.
+/// * `Snapshot`: `snapshot:<id>`, a snapshot of the opponent pool.
+/// * `File`: `file:<path>`, a `.flow` network anywhere on disk.
+#[derive(Clone, Debug)]
+pub(crate) enum Network {
+    Trainee,
+    Snapshot(u32),
+    File(PathBuf),
+}
+
+impl Network {
+    /// The function `load` reads the network from the workspace or from its file.
+    fn load(&self, workspace: &Workspace) -> Result<FeedForward> {
+        match self {
+            Network::Trainee => workspace.load_trainee(),
+            Network::Snapshot(id) => workspace.load(&workspace.opponent_path(*id)),
+            Network::File(path) => workspace.load(path),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Trainee => write!(f, "{}", ratings::TRAINEE),
+            Network::Snapshot(id) => write!(f, "snapshot-{}", id),
+            Network::File(path) => write!(f, "{}", path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy()),
+        }
+    }
+}
+
+/// The enum `AgentSpec` describes a tournament entrant as given on the command line.
+///
+/// Variants:
+///
+/// * `Network`: `trainee`, `snapshot:<id>` or `file:<path>`, a network playing the one-ply search of
+/// `Minimax`. Followed by `@` and limits, it searches with `Minimax::search` within them instead: a
+/// depth, or comma separated `depth=<moves>`, `nodes=<positions>` and `time=<milliseconds>`, e.g.
+/// `snapshot:3@depth=4,nodes=5000`; with no limits it searches to the end of the game.
+/// `search[:<depth>]` is short for `trainee@<depth>`, or for `trainee@` without a depth.
+/// * `Random`: `random`, a player choosing uniformly among the legal moves.
+/// * `Solver`: `solver`, the perfect player, choosing at random among the best moves.
+/// * `Mcts`: `mcts[:<simulations>]`, Monte Carlo tree search with random playouts.
+/// * `Heuristic`: `heuristic[:<skill>]`, the rule-based player of `Heuristic`, following each rule
+/// with probability `skill` (1 by default).
+/// * `Noisy`: `noisy[:<accuracy>]`, a noisy expert playing the solver's move with probability
+/// `accuracy` (0.5 by default) and a random move otherwise.
+/// * `QLearning`: `qlearning[:<episodes>]`, a tabular Q-learning player trained by playing `episodes`
+/// games (50000 by default) against itself before the tournament starts.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Debug)]
+pub(crate) enum AgentSpec {
+    Network(Network, Option<SearchLimits>),
+    Random,
+    Solver,
+    Mcts(u32),
+    Heuristic(f64),
+    Noisy(f64),
+    QLearning(u32),
+}
+
+impl FromStr for AgentSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some((network, limits)) = spec.rsplit_once('@') {
+            return match network.parse() {
+                Ok(AgentSpec::Network(network, None)) => Ok(AgentSpec::Network(network, Some(parse_limits(limits, spec)?))),
+                _ => Err(format!("'{}' is not a network that can search in '{}'", network, spec)),
+            };
+        }
+        let (kind, argument) = match spec.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (spec, None),
+        };
+        let number = |argument: &str| argument.parse().map_err(|_| format!("'{}' is not a valid number in '{}'", argument, spec));
+        match (kind, argument) {
+            ("trainee", None) => Ok(AgentSpec::Network(Network::Trainee, None)),
+            ("snapshot", Some(id)) => Ok(AgentSpec::Network(Network::Snapshot(number(id)?), None)),
+            ("file", Some(path)) if !path.is_empty() => Ok(AgentSpec::Network(Network::File(PathBuf::from(path)), None)),
+            ("random", None) => Ok(AgentSpec::Random),
+            ("solver", None) => Ok(AgentSpec::Solver),
+            ("mcts", None) => Ok(AgentSpec::Mcts(DEFAULT_SIMULATIONS)),
+            ("mcts", Some(simulations)) => Ok(AgentSpec::Mcts(number(simulations)?)),
+            ("heuristic", None) => Ok(AgentSpec::Heuristic(1.0)),
+            ("heuristic", Some(skill)) => match skill.parse() {
+                Ok(skill) if (0.0..=1.0).contains(&skill) => Ok(AgentSpec::Heuristic(skill)),
+                _ => Err(format!("'{}' is not a skill between 0 and 1 in '{}'", skill, spec)),
+            },
+            ("noisy", None) => Ok(AgentSpec::Noisy(DEFAULT_ACCURACY)),
+            ("noisy", Some(accuracy)) => match accuracy.parse() {
+                Ok(accuracy) if (0.0..=1.0).contains(&accuracy) => Ok(AgentSpec::Noisy(accuracy)),
+                _ => Err(format!("'{}' is not an accuracy between 0 and 1 in '{}'", accuracy, spec)),
+            },
+            ("qlearning", None) => Ok(AgentSpec::QLearning(DEFAULT_EPISODES)),
+            ("qlearning", Some(episodes)) => Ok(AgentSpec::QLearning(number(episodes)?)),
+            ("search", None) => Ok(AgentSpec::Network(Network::Trainee, Some(SearchLimits::default()))),
+            ("search", Some(depth)) => Ok(AgentSpec::Network(Network::Trainee, Some(parse_limits(depth, spec)?))),
+            _ => Err(format!(
+                "unknown agent '{}': expected trainee, snapshot:<id>, file:<path>, random, solver, mcts[:<simulations>], heuristic[:<skill>], noisy[:<accuracy>], qlearning[:<episodes>] or search[:<depth>], a network optionally followed by @<limits>",
+                spec
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AgentSpec {
+    /// Formats the agent with the name it is rated under.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentSpec::Network(network, None) => write!(f, "{}", network),
+            AgentSpec::Network(Network::Trainee, Some(SearchLimits { depth, nodes: None, time: None })) => match depth {
+                Some(depth) => write!(f, "search-{}", depth),
+                None => write!(f, "search"),
+            },
+            AgentSpec::Network(network, Some(limits)) => {
+                let limits: Vec<String> = [
+                    limits.depth.map(|depth| format!("depth={}", depth)),
+                    limits.nodes.map(|nodes| format!("nodes={}", nodes)),
+                    limits.time.map(|time| format!("time={}", time.as_millis())),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                write!(f, "{}@{}", network, limits.join(","))
+            }
+            AgentSpec::Random => write!(f, "random"),
+            AgentSpec::Solver => write!(f, "solver"),
+            AgentSpec::Mcts(simulations) => write!(f, "mcts-{}", simulations),
+            AgentSpec::Heuristic(skill) if *skill >= 1.0 => write!(f, "heuristic"),
+            AgentSpec::Heuristic(skill) => write!(f, "heuristic-{}", skill),
+            AgentSpec::Noisy(accuracy) => write!(f, "noisy-{}", accuracy),
+            AgentSpec::QLearning(episodes) => write!(f, "qlearning-{}", episodes),
+        }
+    }
+}
+
+impl AgentSpec {
+    /// The function `instantiate` builds the player described by the spec, loading its network if it
+    /// has one.
+    ///
+    /// Arguments:
+    ///
+    /// * `workspace`: The `workspace` parameter is the run directory the networks are loaded from.
+    /// * `seed`: The `seed` parameter seeds the games a learning player trains on.
+    fn instantiate(&self, workspace: &Workspace, seed: u64) -> Result<Box<dyn Player>> {
+        Ok(match self {
+            AgentSpec::Network(network, limits) => {
+                let mut mm = Minimax::new(network.load(workspace)?);
+                mm.set_tablebase(workspace.load_tablebase()?);
+                match limits {
+                    Some(limits) => Box::new(Search::new(mm, *limits)),
+                    None => Box::new(mm),
+                }
+            }
+            AgentSpec::Random => Box::new(RandomPlayer),
+            AgentSpec::Solver => Box::new(Solver::new()),
+            AgentSpec::Mcts(simulations) => Box::new(Mcts::new(*simulations)),
+            AgentSpec::Heuristic(skill) => Box::new(Heuristic::new(*skill)),
+            AgentSpec::Noisy(accuracy) => Box::new(Expert::Solver.noisy(*accuracy)),
+            AgentSpec::QLearning(episodes) => Box::new(QLearning::train(*episodes, &mut ChaCha8Rng::seed_from_u64(seed))),
+        })
+    }
+}
+
+/// The function `parse_limits` reads the search limits of a network entrant: empty, a depth, or
+/// comma separated `depth=<moves>`, `nodes=<positions>` and `time=<milliseconds>`.
+///
+/// Arguments:
+///
+/// * `limits`: The `limits` parameter is the text to parse.
+/// * `spec`: The `spec` parameter is the whole agent spec, quoted in errors.
+fn parse_limits(limits: &str, spec: &str) -> std::result::Result<SearchLimits, String> {
+    let mut parsed = SearchLimits::default();
+    for limit in limits.split(',').filter(|limit| !limit.is_empty()) {
+        let (key, value) = limit.split_once('=').unwrap_or(("depth", limit));
+        let invalid = || format!("'{}' is not a valid search limit in '{}'", limit, spec);
+        match key {
+            "depth" => parsed.depth = Some(value.parse().map_err(|_| invalid())?),
+            "nodes" => parsed.nodes = Some(value.parse().map_err(|_| invalid())?),
+            "time" => parsed.time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?)),
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(parsed)
+}
+
+/// A game of a tournament: the entrants playing X and O, and the score of X.
+pub(crate) type Game = (usize, usize, f64);
+
+/// The struct `Record` counts the games of one entrant against another.
+///
+/// Properties:
+///
+/// * `wins`: The `wins` property is the number of games won.
+/// * `draws`: The `draws` property is the number of games drawn.
+/// * `losses`: The `losses` property is the number of games lost.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    /// The function `games` returns the number of games played.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The function `score` returns the fraction of the points scored, counting a draw as half a win.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    /// The function `confidence` returns the half width of the 95% confidence interval of `score`,
+    /// using the normal approximation of the per-game scores.
+    pub fn confidence(&self) -> f64 {
+        let games = self.games() as f64;
+        if games < 2.0 {
+            return 1.0;
+        }
+        let p = self.score();
+        let variance = (self.wins as f64 * (1.0 - p).powi(2) + self.draws as f64 * (0.5 - p).powi(2) + self.losses as f64 * p.powi(2)) / games;
+        1.96 * (variance / (games - 1.0)).sqrt()
+    }
+
+    /// The function `add` counts a game with the given result: 1, 0 or -1.
+    fn add(&mut self, result: i8) {
+        match result {
+            1 => self.wins += 1,
+            0 => self.draws += 1,
+            _ => self.losses += 1,
+        }
+    }
+
+    /// The function `merge` adds the games of another record.
+    pub fn merge(&mut self, other: &Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    /// The function `reversed` returns the record seen from the opponent.
+    fn reversed(&self) -> Record {
+        Record {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+}
+
+/// The struct `Tournament` is a round robin between agents: every pair plays `games` openings, each
+/// once with both colors.
+///
+/// Properties:
+///
+/// * `agents`: The `agents` property lists the entrants.
+/// * `games`: The `games` property is the number of game pairs per pairing.
+/// * `random_plies`: The `random_plies` property is the number of random moves opening each game pair,
+/// so that deterministic agents do not replay the same game.
+/// * `seed`: The `seed` property seeds the openings and the random choices of the agents.
+/// * `book`: The `book` property is the opening book every agent plays from, if any, with the number
+/// of plies it is consulted for.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct Tournament {
+    pub agents: Vec<AgentSpec>,
+    pub games: u32,
+    pub random_plies: u8,
+    pub seed: u64,
+    pub book: Option<(Arc<OpeningBook>, u8)>,
+}
+
+/// The struct `CrossTable` is the outcome of a tournament.
+///
+/// Properties:
+///
+/// * `names`: The `names` property holds the rating name of each entrant.
+/// * `records`: The `records` property holds, for each entrant, its record against every entrant.
+/// * `games`: The `games` property lists every game as the two entrants, X first, and the score of X,
+/// in the order they were played.
+#[allow(clippy::doc_lazy_continuation)]
+pub(crate) struct CrossTable {
+    pub names: Vec<String>,
+    pub records: Vec<Vec<Record>>,
+    pub games: Vec<Game>,
+}
+
+impl CrossTable {
+    /// The function `total` returns the record of an entrant against the whole field.
+    pub fn total(&self, agent: usize) -> Record {
+        let mut total = Record::default();
+        for record in &self.records[agent] {
+            total.merge(record);
+        }
+        total
+    }
+
+    /// The function `rate` adds every game of the tournament to the ratings.
+    pub fn rate(&self, ratings: &mut Ratings) {
+        for (x, o, score) in &self.games {
+            ratings.record(&self.names[*x], &self.names[*o], *score);
+        }
+    }
+}
+
+impl Tournament {
+    /// The function `run` plays the tournament, one pairing per rayon task.
+    ///
+    /// Arguments:
+    ///
+    /// * `workspace`: The `workspace` parameter is the run directory the networks are loaded from.
+    ///
+    /// Returns:
+    ///
+    /// The cross table, or the first error raised while loading an agent.
+    pub fn run(&self, workspace: &Workspace) -> Result<CrossTable> {
+        let n = self.agents.len();
+        // Learning entrants are trained once and copied into each of their pairings.
+        let learners: Vec<Option<QLearning>> = self
+            .agents
+            .par_iter()
+            .map(|agent| match agent {
+                AgentSpec::QLearning(episodes) => Some(QLearning::train(*episodes, &mut ChaCha8Rng::seed_from_u64(self.seed))),
+                _ => None,
+            })
+            .collect();
+        let pairings: Vec<(usize, usize)> = (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b))).collect();
+        let results = pairings
+            .par_iter()
+            .enumerate()
+            .map(|(k, (a, b))| self.play_pairing(workspace, &learners, *a, *b, k as u64))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut records = vec![vec![Record::default(); n]; n];
+        let mut games = Vec::new();
+        for ((a, b), (record, played)) in pairings.iter().zip(results) {
+            records[*a][*b] = record;
+            records[*b][*a] = record.reversed();
+            games.extend(played);
+        }
+        Ok(CrossTable {
+            names: self.agents.iter().map(|agent| agent.to_string()).collect(),
+            records,
+            games,
+        })
+    }
+
+    /// The function `play_pairing` plays every game between two entrants.
+    ///
+    /// Returns:
+    ///
+    /// The record of `a` against `b` and the games played, or the error raised while loading them.
+    fn play_pairing(&self, workspace: &Workspace, learners: &[Option<QLearning>], a: usize, b: usize, stream: u64) -> Result<(Record, Vec<Game>)> {
+        let mut first = self.entrant(workspace, learners, a)?;
+        let mut second = self.entrant(workspace, learners, b)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(stream);
+
+        let mut record = Record::default();
+        let mut games = Vec::new();
+        for _ in 0..self.games {
+            let opening = self.opening(&mut rng);
+            let game = player::play_match(first.as_mut(), second.as_mut(), &opening, None, &mut rng);
+            record.add(game.result);
+            games.push((a, b, game.x_score()));
+            let game = player::play_match(second.as_mut(), first.as_mut(), &opening, None, &mut rng);
+            record.add(-game.result);
+            games.push((b, a, game.x_score()));
+        }
+        Ok((record, games))
+    }
+
+    /// The function `entrant` builds the player of an entrant, or copies it from `learners` if it was
+    /// trained for the tournament, playing from the opening book if the tournament has one.
+    fn entrant(&self, workspace: &Workspace, learners: &[Option<QLearning>], agent: usize) -> Result<Box<dyn Player>> {
+        let player: Box<dyn Player> = match &learners[agent] {
+            Some(learner) => Box::new(learner.clone()),
+            None => self.agents[agent].instantiate(workspace, self.seed)?,
+        };
+        Ok(match &self.book {
+            Some((book, plies)) => Box::new(Booked { player, book: book.clone(), plies: *plies }),
+            None => player,
+        })
+    }
+
+    /// The function `opening` draws `random_plies` random moves. A game cannot end within them, as
+    /// `random_plies` is at most four.
+    fn opening(&self, rng: &mut ChaCha8Rng) -> Vec<i16> {
+        let mut board = Board::new();
+        (0..self.random_plies.min(4))
+            .map(|_| {
+                let _move = random_move(&board, rng);
+                board.play_move(_move);
+                _move
+            })
+            .collect()
+    }
+}
+
+/// The function `play_series` plays games between two agents with fixed colors.
+///
+/// Arguments:
+///
+/// * `workspace`: The `workspace` parameter is the run directory the networks are loaded from.
+/// * `agent`: The `agent` parameter is the agent whose record is returned.
+/// * `opponent`: The `opponent` parameter is the agent it plays against.
+/// * `games`: The `games` parameter is the number of games to play.
+/// * `as_x`: The `as_x` parameter tells whether `agent` moves first.
+/// * `seed`: The `seed` parameter seeds the random choices of the agents.
+///
+/// Returns:
+///
+/// The record of `agent`, or the error raised while loading the agents.
+pub(crate) fn play_series(workspace: &Workspace, agent: &AgentSpec, opponent: &AgentSpec, games: u32, as_x: bool, seed: u64) -> Result<Record> {
+    let mut agent = agent.instantiate(workspace, seed)?;
+    let mut opponent = opponent.instantiate(workspace, seed)?;
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut record = Record::default();
+    for _ in 0..games {
+        if as_x {
+            record.add(player::play_match(agent.as_mut(), opponent.as_mut(), &[], None, &mut rng).result);
+        } else {
+            record.add(-player::play_match(opponent.as_mut(), agent.as_mut(), &[], None, &mut rng).result);
+        }
+    }
+    Ok(record)
+}
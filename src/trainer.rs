@@ -1,212 +1,499 @@
-use std::{fs, io};
+use std::{fmt, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, mpsc::Sender, Arc}, thread, time::{Duration, Instant}};
 
-use neuroflow::{data::DataSet, FeedForward};
+use log::{debug, error, info, warn};
+use neuroflow::{data::{DataSet, Extractable}, FeedForward};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+
+use crate::{board::Board, config::TrainerConfig, curriculum::{Curriculum, Stage}, error::{Error, Result}, evaluation::{Accuracy, Evaluator}, game_tree::GameTree, metrics::{GameMetrics, GateMetrics, IterationMetrics, Metric, MetricsLog}, heuristic::Heuristic, minimax::{Minimax, SearchLimits}, player::{self, Exploring, Player, RandomPlayer, Search}, pool::OpponentPool, state::{RngState, TrainerState}, tablebase::Tablebase, tournament::{AgentSpec, Network, Tournament}, workspace::Workspace};
+
+/// How many times a failing model read or write is attempted before training gives up.
+const IO_ATTEMPTS: u32 = 3;
+/// The pause before the first retry, doubled for every further attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// The accuracy of the noisy expert when `noisy_expert_opponent` is absent, as in a `noisy-expert`
+/// stage of the curriculum.
+const DEFAULT_ACCURACY: f64 = 0.5;
+/// The random moves opening each game pair of a gate match, so that the two networks do not replay
+/// the same game.
+const GATE_RANDOM_PLIES: u8 = 2;
+
+/// The enum `Opponent` is who the trainee plays a training game against.
+///
+/// Variants:
+///
+/// * `Snapshot`: The snapshot of the opponent pool with the given id.
+/// * `Heuristic`: The rule-based `Heuristic` player, with the skill of `heuristic_opponent`.
+/// * `Random`: The player choosing uniformly among the legal moves.
+/// * `NoisyExpert`: The noisy expert following `noisy_expert`, with the accuracy of
+/// `noisy_expert_opponent`, or 0.5 when it is absent.
+/// * `Search`: The trainee itself, searching `search_depth` moves ahead.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Opponent {
+    Snapshot(u32),
+    Heuristic,
+    Random,
+    NoisyExpert,
+    Search,
+}
+
+impl fmt::Display for Opponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Opponent::Snapshot(id) => write!(f, "snapshot-{}", id),
+            Opponent::Heuristic => write!(f, "heuristic"),
+            Opponent::Random => write!(f, "random"),
+            Opponent::NoisyExpert => write!(f, "noisy-expert"),
+            Opponent::Search => write!(f, "search"),
+        }
+    }
+}
+
+impl Serialize for Opponent {
+    /// Serializes the opponent as its name, e.g. `snapshot-3`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
-use crate::{board::Board, game_tree::GameTree, minimax::Minimax};
 pub(crate) struct Trainer{
     trainee: FeedForward,
-    storer_no: i16,
-    total_no: i16,
+    workspace: Workspace,
+    config: TrainerConfig,
+    pool: OpponentPool,
+    iteration: i16,
+    rng: ChaCha8Rng,
+    evaluator: Option<Evaluator>,
+    tablebase: Option<Arc<Tablebase>>,
+    curriculum: Curriculum,
+    metrics: Option<MetricsLog>,
+    started: Instant,
+    observer: Option<Sender<Metric>>,
+    stop: Arc<AtomicBool>,
 }
 
 impl Trainer {
     /// The function `new` creates a new `Trainer` instance with a specified `FeedForward` neural
-    /// network and default values for `storer_no` and `total_no`.
+    /// network and an empty opponent pool.
     /// 
     /// Arguments:
     /// 
     /// * `nn`: The `nn` parameter in the `new` function is of type `FeedForward`. It is used to
     /// initialize the `trainee` field of the `Trainer` struct.
+    /// * `workspace`: The `workspace` parameter is the run directory all models of this training run are
+    /// read from and written to.
+    /// * `config`: The `config` parameter holds the hyperparameters used by `train`.
     /// 
     /// Returns:
     /// 
     /// A new instance of the `Trainer` struct is being returned with the specified values for the
-    /// `trainee`, `storer_no`, and `total_no` fields.
-    pub fn new(nn: FeedForward) -> Self {
+    /// `trainee`, `workspace` and `config` fields.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn new(nn: FeedForward, workspace: Workspace, config: TrainerConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
         Trainer {  
             trainee: nn,
-            storer_no: 100,
-            total_no: 100,
+            workspace,
+            pool: OpponentPool::new(config.size.max(1) as usize),
+            config,
+            iteration: 0,
+            rng,
+            evaluator: None,
+            tablebase: None,
+            curriculum: Curriculum::default(),
+            metrics: None,
+            started: Instant::now(),
+            observer: None,
+            stop: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    /// The function `spawn_trainers` creates and saves neural network trainers based on the specified
-    /// size.
+    /// The function `config` returns the configuration of the run.
+    pub fn config(&self) -> &TrainerConfig {
+        &self.config
+    }
+
+    /// The function `observe` sends a copy of every metric of the run to `observer`, such as the
+    /// training dashboard.
+    pub fn observe(&mut self, observer: Sender<Metric>) {
+        self.observer = Some(observer);
+    }
+
+    /// The function `stop_handle` returns a flag that, once set, makes training stop after the
+    /// current iteration. The run can then be continued with `resume`.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// The `reset` function in Rust deletes the opponent pool of the workspace.
+    /// 
+    /// Returns:
+    /// 
+    /// The `reset` function returns a `Result`. The `Ok(())` value is returned if the function
+    /// executes successfully without any errors.
+    pub fn reset(&self) -> Result<()> {
+        self.workspace.clear_pool()
+    }
+
+    /// The function `store_snapshot` adds a copy of the trainee to the opponent pool.
     /// 
     /// Arguments:
     /// 
-    /// * `size`: The `size` parameter in the `spawn_trainers` function represents the number of
-    /// trainers that will be spawned. It is used to determine how many instances of the `FeedForward`
-    /// neural network model will be created and saved to files.
-    pub fn spawn_trainers(&self, size: i16) {
-        for i in 0..size {
-            let mut nn: FeedForward= neuroflow::io::load("src/Trainee/acordion.flow").unwrap();
-            let y= format!("src/Trainers/acordion-trainers-{}.flow", i);
-            neuroflow::io::save(&mut nn, &y).unwrap();
+    /// * `iteration`: The `iteration` parameter is the iteration the snapshot is taken at.
+    pub fn store_snapshot(&mut self, iteration: i16) -> Result<()> {
+        let id = retry_io(|| self.pool.add(&self.workspace, &mut self.trainee, iteration))?;
+        let rates: Vec<String> = self.pool.snapshots().iter().map(|snapshot| format!("{}: {:.2}", snapshot.id, snapshot.win_rate())).collect();
+        info!("Added snapshot {} to the opponent pool (trainee win rates {})", id, rates.join(", "));
+        Ok(())
+    }
+
+    /// The `train` function in Rust is used to start the training process by seeding the opponent
+    /// pool with the trainee, and then executing the middle trainer achiever with the iteration count
+    /// and ghost steps of the trainer configuration.
+    /// 
+    /// Returns:
+    /// 
+    /// `Ok(())` once all iterations ran, otherwise the error that stopped training. The trainee has
+    /// been checkpointed by then whenever that was possible.
+    pub fn train(&mut self) -> Result<()> {
+        let TrainerConfig { size, iterations, ghost_steps, .. } = self.config;
+        info!("Training starts");
+        self.metrics = Some(MetricsLog::open(&self.workspace.metrics_path(), None)?);
+        self.tablebase = self.workspace.load_tablebase()?;
+        self.started = Instant::now();
+        self.pool = OpponentPool::new(size.max(1) as usize);
+        self.store_snapshot(0)?;
+        // The candidate of a gated run starts from the champion.
+        let path = self.model_path();
+        retry_io(|| self.workspace.save(&mut self.trainee, &path))?;
+        self.iteration = 0;
+        self.curriculum = Curriculum::default();
+        self.save_state()?;
+        if self.middle_trainer_achiever(iterations, ghost_steps)? {
+            self.finish()?;
+        }
+        Ok(())
+    }
 
+    /// The `resume` function continues a run interrupted during `train` from the state saved after its
+    /// last completed iteration. The trainee given to `new` should be the one loaded from
+    /// `Workspace::load_checkpoint`, and the snapshots of the pool of the state must still be in the
+    /// workspace.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `state`: The `state` parameter is the state read with `Workspace::load_state`.
+    pub fn resume(&mut self, state: TrainerState) -> Result<()> {
+        let TrainerConfig { iterations, ghost_steps, .. } = self.config;
+        self.rng = state.rng.restore().ok_or_else(|| Error::State {
+            path: self.workspace.state_path(),
+            message: "the random generator state is corrupted".to_string(),
+        })?;
+        self.pool = state.pool;
+        // The size may have been overridden on the command line.
+        self.pool.set_capacity(self.config.size.max(1) as usize);
+        self.iteration = state.iteration;
+        self.curriculum = state.curriculum;
+        // The games load the trainee from disk, which may be ahead of the checkpoint.
+        let path = self.model_path();
+        retry_io(|| self.workspace.save(&mut self.trainee, &path))?;
+        info!("Resuming training at iteration {}", self.iteration);
+        self.metrics = Some(MetricsLog::open(&self.workspace.metrics_path(), Some(self.iteration))?);
+        self.tablebase = self.workspace.load_tablebase()?;
+        self.started = Instant::now();
+        if self.middle_trainer_achiever(iterations, ghost_steps)? {
+            self.finish()?;
         }
+        Ok(())
+    }
 
+    /// The function `finish` clears the saved state of a completed run and makes sure the final
+    /// trainee, or candidate of a gated run, is on disk. The opponent pool is kept until the next run
+    /// starts.
+    fn finish(&mut self) -> Result<()> {
+        let path = self.model_path();
+        retry_io(|| self.workspace.save(&mut self.trainee, &path))?;
+        self.workspace.clear_state()
     }
 
-    /// The `reset` function in Rust deletes all files in the "src/Trainers" and "src/Storers"
-    /// directories.
+    /// The function `save_state` writes the iteration counter, configuration, random generator and
+    /// opponent pool of the run, together with a checkpoint of the trainee, so that `resume` can pick
+    /// up from here.
+    pub fn save_state(&mut self) -> Result<()> {
+        let state = TrainerState {
+            iteration: self.iteration,
+            config: self.config.clone(),
+            rng: RngState::capture(&self.rng),
+            curriculum: self.curriculum.clone(),
+            pool: self.pool.clone(),
+        };
+        retry_io(|| self.workspace.save_state(&state, &mut self.trainee))
+    }
+
+    /// The function `middle_trainer_achiever` iterates a specified number of times, training against
+    /// opponents drawn from the pool and adding snapshots of the trainee to it periodically.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `iterations`: The `iterations` parameter specifies the total number of iterations the
+    /// `middle_trainer_achiever` function will run for. Each iteration includes training for opponents,
+    /// updating the pool statistics, and potentially taking a snapshot based on `ghost_steps`.
+    /// * `ghost_steps`: The `ghost_steps` parameter determines how often the `store_snapshot` method is
+    /// called during the iterations: after every `ghost_steps` completed iterations.
+    /// 
+    /// The iterations start at the iteration counter of the trainer and the state of the run is saved
+    /// after each of them. If an iteration fails even after retrying, the trainee is checkpointed and
+    /// the error returned.
     /// 
     /// Returns:
     /// 
-    /// The `reset` function returns a `Result` type from the `io` module. The `Ok(())` value is
-    /// returned if the function executes successfully without any errors.
-    pub fn reset(&self) -> io::Result<()> {
-        for entry in fs::read_dir("src/Trainers")? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                fs::remove_file(path)?;
+    /// `true` once all iterations ran, or `false` if training was stopped through `stop_handle`.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn middle_trainer_achiever(&mut self, iterations: i16, ghost_steps : i16) -> Result<bool> {
+        for i in self.iteration..iterations {
+            if self.stop.load(Ordering::Relaxed) {
+                info!("Training stopped before iteration {}; continue it with `train --resume`", i);
+                return Ok(false);
+            }
+            debug!("Starting iteration {}", i);
+            if let Err(e) = self.run_iteration(i, ghost_steps) {
+                error!("Iteration {} failed: {}", i, e);
+                match self.checkpoint() {
+                    Ok(path) => warn!("Trainee checkpointed to {}", path.display()),
+                    Err(e) => error!("Could not checkpoint the trainee: {}", e),
+                }
+                return Err(e);
             }
+            self.iteration = i + 1;
+            self.save_state()?;
         }
-        for entry in fs::read_dir("src/Storers")? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// The function `run_iteration` runs a single iteration of `middle_trainer_achiever`, logs its
+    /// metrics and promotes the trainee to the next stage of the curriculum once it has earned it.
+    fn run_iteration(&mut self, i: i16, ghost_steps : i16) -> Result<()> {
+        let start = Instant::now();
+        let stage = self.curriculum.stage(&self.config);
+        let mut opponents: Vec<Opponent> = match stage {
+            None | Some(Stage::Pool) => self
+                .pool
+                .sample(self.config.sampling, self.config.opponents.max(1) as usize, &mut self.rng)
+                .into_iter()
+                .map(Opponent::Snapshot)
+                .collect(),
+            Some(Stage::Random) => vec![Opponent::Random],
+            Some(Stage::NoisyExpert) => vec![Opponent::NoisyExpert],
+            Some(Stage::Heuristic) => vec![Opponent::Heuristic],
+            Some(Stage::Search) => vec![Opponent::Search],
+        };
+        let extras = [
+            (self.config.heuristic_opponent.is_some(), Opponent::Heuristic),
+            (self.config.random_opponent, Opponent::Random),
+            (self.config.noisy_expert_opponent.is_some(), Opponent::NoisyExpert),
+        ];
+        for (wanted, opponent) in extras {
+            if wanted && !opponents.contains(&opponent) {
+                opponents.push(opponent);
             }
         }
+        let (games, games_per_second) = self.train_for_opponents(i, &opponents)?;
+        if (i + 1) % ghost_steps.max(1) == 0 {
+            self.store_snapshot(i + 1)?;
+        }
+        let accuracy = if self.config.eval_interval > 0 && (i + 1) % self.config.eval_interval == 0 {
+            Some(self.evaluate(i)?)
+        } else {
+            None
+        };
+
+        let mut metrics = IterationMetrics::new(i, &games, accuracy.as_ref());
+        metrics.games_per_second = games_per_second;
+        metrics.seconds = start.elapsed().as_secs_f64();
+        metrics.elapsed = self.started.elapsed().as_secs_f64();
+        metrics.stage = stage;
+        info!(
+            "Iteration {}: W/D/L {}/{}/{}, loss {:.4} -> {:.4}, {:.1} games/s, {:.2}s",
+            i, metrics.wins, metrics.draws, metrics.losses, metrics.loss_before, metrics.loss_after, metrics.games_per_second, metrics.seconds
+        );
+        self.log_metric(Metric::Iteration(metrics))?;
+
+        if let Some(promotion) = self.curriculum.record(&self.config, i, &games) {
+            info!(
+                "Iteration {}: promoted from the {} stage to the {} stage (score {:.2})",
+                i, promotion.from, promotion.to, promotion.score
+            );
+            self.log_metric(Metric::Promotion(promotion))?;
+        }
+        if self.config.gate_interval > 0 && (i + 1) % self.config.gate_interval == 0 {
+            self.gate(i)?;
+        }
         Ok(())
     }
 
-    /// The function `store_storer` loads a neural network model, saves it to a file with a specific
-    /// name based on the storer number, and increments the storer number.
-    pub fn store_storer(&mut self) {
-        let mut nn: FeedForward = neuroflow::io::load("src/Trainee/acordion.flow").unwrap();
-        let y= format!("src/Storers/acordion-storers-{}.flow", self.storer_no);
-        neuroflow::io::save(&mut nn, &y).unwrap();
-        if self.storer_no == self.total_no {
-            self.storer_no = 0;
+    /// The function `model_path` returns the file the trainee is trained into: the candidate when
+    /// training is gated, the trainee of the workspace otherwise.
+    fn model_path(&self) -> PathBuf {
+        if self.config.gate_interval > 0 {
+            self.workspace.candidate_path()
         } else {
-            self.storer_no += 1;
+            self.workspace.trainee_path()
         }
     }
 
-    /// The function `spawn_storers` creates and saves neural network models for storing data.
-    /// 
-    /// Arguments:
-    /// 
-    /// * `size`: The `size` parameter in the `spawn_storers` function represents the number of storers
-    /// to be spawned. It is used to determine how many storers will be created and saved to files with
-    /// unique names.
-    pub fn spawn_storers (&self, size: i16) {
-        for i in 0..size {
-            let mut nn: FeedForward= FeedForward::new(&[9, 16, 32, 16, 1]);
-            let y= format!("src/Storers/acordion-storers-{}.flow", i);
-            neuroflow::io::save(&mut nn, &y).unwrap();
+    /// The function `gate` plays the candidate, as saved after the games of iteration `i`, against the
+    /// champion, and makes it the new champion if it scores above `gate_threshold`.
+    fn gate(&mut self, i: i16) -> Result<()> {
+        let tournament = Tournament {
+            agents: vec![AgentSpec::Network(Network::File(self.workspace.candidate_path()), None), AgentSpec::Network(Network::Trainee, None)],
+            games: self.config.gate_games.max(1),
+            random_plies: GATE_RANDOM_PLIES,
+            seed: self.rng.gen(),
+            book: None,
+        };
+        let record = retry_io(|| tournament.run(&self.workspace))?.records[0][1];
+        let promoted = record.score() > self.config.gate_threshold;
+        if promoted {
+            retry_io(|| self.workspace.save_trainee(&mut self.trainee))?;
         }
+        info!(
+            "Iteration {}: the candidate scored {:.2} ({}/{}/{}) against the champion; {}",
+            i,
+            record.score(),
+            record.wins,
+            record.draws,
+            record.losses,
+            if promoted { "it becomes the new champion" } else { "the champion is kept" }
+        );
+        let gate = GateMetrics { iteration: i, wins: record.wins, draws: record.draws, losses: record.losses, score: record.score(), promoted };
+        self.log_metric(Metric::Gate(gate))
     }
 
-    /// The function `change_trainers` iterates through a range of values, loads a neural network model
-    /// from a file, and saves it to a new file with a different name.
+    /// The function `evaluate` measures the trainee, as saved after the games of iteration `i`,
+    /// against the perfect solver.
+    fn evaluate(&mut self, i: i16) -> Result<Accuracy> {
+        // The accuracy is that of the network, so the search does not probe the tablebase.
+        let mut mm = Minimax::new(retry_io(|| self.workspace.load(&self.model_path()))?);
+        // Solving every position is only worth it once evaluation is actually used.
+        let accuracy = self.evaluator.get_or_insert_with(Evaluator::new).evaluate(&mut mm);
+        info!("Iteration {}: {}", i, accuracy);
+        Ok(accuracy)
+    }
+
+    /// The function `log_metric` writes a line to the metrics log of the run, if one is open, and
+    /// passes it on to the observer.
+    fn log_metric(&mut self, metric: Metric) -> Result<()> {
+        if let Some(observer) = &self.observer {
+            // The observer may have gone away; training goes on regardless.
+            let _ = observer.send(metric.clone());
+        }
+        match self.metrics.as_mut() {
+            Some(metrics) => metrics.write(&metric),
+            None => Ok(()),
+        }
+    }
+
+    /// The function `checkpoint` saves the trainee held in memory, falling back to a file of the
+    /// temporary directory named after the process when the workspace cannot be written, so that runs
+    /// of different workspaces never rescue their trainee to the same file.
     /// 
-    /// Arguments:
+    /// Returns:
     /// 
-    /// * `size`: The `size` parameter in the `change_trainers` function represents the number of
-    /// trainers that you want to change. It is used to iterate over a range from 0 to `size` in order
-    /// to load and save neural network models for each trainer.
-    pub fn change_trainers(&self, size: i16) {
-        for i in 0..size {
-            let y1 = format!("src/Storers/acordion-storers-{}.flow", i);
-            let y2 = format!("src/Storers/acordion-trainers-{}.flow", i);
-            let mut nn: FeedForward = neuroflow::io::load(&y1).unwrap();
-            neuroflow::io::save(&mut nn, &y2).unwrap();
+    /// The path the trainee was saved to, or the error raised by the fallback location.
+    pub fn checkpoint(&mut self) -> Result<PathBuf> {
+        let path = self.model_path();
+        if retry_io(|| self.workspace.save(&mut self.trainee, &path)).is_ok() {
+            return Ok(path);
         }
+        let rescue = std::env::temp_dir().join(format!("acordion-rescue-{}.flow", std::process::id()));
+        self.workspace.save(&mut self.trainee, &rescue)?;
+        Ok(rescue)
     }
 
-    /// The `train` function in Rust is used to start the training process by spawning storers and
-    /// trainers, and then executing the middle trainer achiever with specified parameters.
+    /// The function `train_for_opponents` trains the model against the given opponents.
+    /// The games against all opponents are first played in parallel, then the trainee is updated on
+    /// each of them in turn, so the network only ever has a single writer. The result of every game
+    /// against a snapshot is recorded in the pool.
     /// 
     /// Arguments:
     /// 
-    /// * `size`: The `size` parameter represents the size of the training environment or dataset. It is
-    /// used to determine the number of storers and trainers to spawn in the training process.
-    /// * `iterations`: The `iterations` parameter in the `train` function represents the number of
-    /// training iterations or epochs that will be performed during the training process. Each iteration
-    /// typically involves one forward pass and one backward pass of all the training examples.
-    /// * `ghost_steps`: The `ghost_steps` parameter in the `train` function likely represents the
-    /// number of steps taken by a ghost entity during training. This parameter is used in the
-    /// `middle_trainer_achiever` method to control the behavior of the ghost entity during the training
-    /// process.
-    /// * `reset_steps`: The `reset_steps` parameter in the `train` function represents the number of
-    /// steps after which a reset operation will be triggered during the training process.
-    pub fn train(&mut self, size: i16, iterations: i16, ghost_steps : i16, reset_steps : i16){
-        println!("Training starts");
-        self.spawn_storers(size);
-        self.spawn_trainers(size);
-        self.middle_trainer_achiever(size, iterations, ghost_steps, reset_steps);
-        let _ = self.reset();
-    }
-
-    /// The function `middle_trainer_achiever` iterates a specified number of times, training for
-    /// opponents, storing data periodically, and changing trainers at certain intervals.
+    /// * `iteration`: The `iteration` parameter is the current iteration, recorded with the games.
+    /// * `opponents`: The `opponents` parameter lists the opponents to play against.
     /// 
-    /// Arguments:
+    /// Returns:
     /// 
-    /// * `size`: The `size` parameter represents the size of the training data or the number of
-    /// opponents to train against.
-    /// * `iterations`: The `iterations` parameter specifies the total number of iterations the
-    /// `middle_trainer_achiever` function will run for. Each iteration includes training for opponents,
-    /// storing data, and potentially changing trainers based on the `ghost_steps` and `reset_steps`
-    /// conditions.
-    /// * `ghost_steps`: The `ghost_steps` parameter determines how often the `store_storer` method is
-    /// called during the iterations. If `i % ghost_steps == 0`, then the `store_storer` method will be
-    /// called.
-    /// * `reset_steps`: The `reset_steps` parameter determines after how many iterations the trainers
-    /// should be reset in the `middle_trainer_achiever` function. When the current iteration count is a
-    /// multiple of `reset_steps`, the trainers will be changed/reset.
-    pub fn middle_trainer_achiever(&mut self, size: i16, iterations: i16, ghost_steps : i16, reset_steps : i16) {
-        for i in 0..iterations {
-            println!("Starting iteration {}", i);
-            self.train_for_opponents(size);
-            //self.change_trainers(size);
-            if i % ghost_steps == 0 {
-                self.store_storer();
-            }
-            if i % reset_steps == 0 {
-                self.change_trainers(size);
+    /// The metrics of the games and the number of games played per second.
+    pub fn train_for_opponents(&mut self, iteration: i16, opponents: &[Opponent]) -> Result<(Vec<GameMetrics>, f64)> {
+        // One seed per game pair, drawn up front so the games do not depend on thread scheduling.
+        let pairs = opponents.len() * self.config.games_per_opponent.max(1) as usize;
+        let seeds: Vec<u64> = (0..pairs).map(|_| self.rng.gen()).collect();
+
+        let start = Instant::now();
+        let trees = self.play_for_opponents(opponents, &seeds)?;
+        let elapsed = start.elapsed().as_secs_f64();
+        let games_per_second = trees.len() as f64 / elapsed.max(f64::EPSILON);
+        debug!("Played {} games in {:.2}s ({:.1} games/s)", trees.len(), elapsed, games_per_second);
+
+        let mut games = Vec::new();
+        for (opponent, tree) in trees {
+            if let Opponent::Snapshot(id) = opponent {
+                self.pool.record(id, (tree.get_result() + 1) as f64 / 2.0);
             }
+            let (length, result, final_position) = (tree.get_length(), tree.get_result(), tree.get_final_position());
+            let (loss_before, loss_after) = self.train_for_tree(tree)?;
+            let game = GameMetrics { iteration, opponent, length, result, final_position, loss_before, loss_after };
+            debug!("Game against {}: result {}, {} moves, loss {:.4} -> {:.4}", opponent, result, length, loss_before, loss_after);
+            self.log_metric(Metric::Game(game.clone()))?;
+            games.push(game);
         }
+        Ok((games, games_per_second))
     }
 
-    /// The function `train_for_opponents` trains the model iteratively for a specified number of
-    /// opponents.
+    /// The function `play_for_opponents` plays `games_per_opponent` game pairs against every given
+    /// opponent on the rayon thread pool.
     /// 
     /// Arguments:
     /// 
-    /// * `size`: The `size` parameter in the `train_for_opponents` function represents the number of
-    /// opponents for which the training will be conducted. It is used to determine the range of
-    /// opponents to iterate over and train against.
-    pub fn train_for_opponents(&mut self, size: i16) {
-        for i in 0..size{
-            println!("Opponent {}", i);
-            let y2 = format!("src/Trainers/acordion-trainers-{}.flow", i);
-            self.train_iteratively(&y2);
-        }
+    /// * `opponents`: The `opponents` parameter lists the opponents to play against.
+    /// * `seeds`: The `seeds` parameter holds the seed of each game pair, opponent by opponent.
+    /// 
+    /// Returns:
+    /// 
+    /// The game trees of all games with the opponent they were played against, in the order of
+    /// `seeds`, or the first error raised while loading the players.
+    fn play_for_opponents(&self, opponents: &[Opponent], seeds: &[u64]) -> Result<Vec<(Opponent, GameTree)>> {
+        let games = self.config.games_per_opponent.max(1) as usize;
+        let pairs = seeds
+            .par_iter()
+            .enumerate()
+            .map(|(k, seed)| {
+                let opponent = opponents[k / games];
+                let trees = self.play_game_pair(opponent, *seed)?;
+                Ok(trees.map(|tree| (opponent, tree)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pairs.into_iter().flatten().collect())
     }
     
-    /// The function `train_iteratively` trains a model iteratively by playing games with two different
-    /// types and updating the model based on the results.
+    /// The function `play_game_pair` plays one game with each of the two types against an opponent.
     /// 
     /// Arguments:
     /// 
-    /// * `trainer`: The `trainer` parameter in the `train_iteratively` function is a string that
-    /// specifies the type of trainer being used for training the model. It is used to determine which
-    /// training method to apply during the iterative training process.
-    pub fn train_iteratively(&mut self, trainer: &str) {
-        for _i in 0..1 {
-            let tree= self.play_game_with_type_1(trainer);
-            let tree2 = self.play_game_with_type_2(trainer);
-            
-            self.train_for_tree(tree);
-            self.train_for_tree(tree2)
-        }
+    /// * `opponent`: The `opponent` parameter is the opponent the trainee plays against.
+    /// * `seed`: The `seed` parameter seeds the exploration of both games and the random choices of
+    /// the opponent.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn play_game_pair(&self, opponent: Opponent, seed: u64) -> Result<[GameTree; 2]> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let tree= self.play_game_with_type_1(opponent, &mut rng)?;
+        let tree2 = self.play_game_with_type_2(opponent, &mut rng)?;
+        Ok([tree, tree2])
     }
 
     /// The function `train_for_tree` trains a neural network model using data from a game tree and
@@ -216,14 +503,40 @@ impl Trainer {
     /// 
     /// * `tree`: The `tree` parameter in the `train_for_tree` function is of type `GameTree`. It is
     /// used as input to calculate data and create a dataset for training a neural network model.
-    fn train_for_tree(&mut self, tree: GameTree) {
+    /// 
+    /// Returns:
+    /// 
+    /// The loss of the trainee on the game before and after training on it.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn train_for_tree(&mut self, tree: GameTree) -> Result<(f64, f64)> {
         let (positions, predicted_rewards) = self.calculate_data(tree);
     
         
         let data = self.create_dataset(positions, predicted_rewards);
     
-        self.trainee.activation(neuroflow::activators::Type::Tanh).learning_rate(0.01).train(&data, 50_000);
-        neuroflow::io::save(&mut self.trainee, "src/Trainee/acordion.flow").unwrap();
+        self.trainee.activation(neuroflow::activators::Type::Tanh).learning_rate(self.config.learning_rate);
+        let loss_before = self.loss(&data);
+        // Same as `FeedForward::train`, but sampling with the trainer's own generator so that its state
+        // can be saved and restored.
+        for _ in 0..self.config.epochs {
+            let (x, y) = data.get(self.rng.gen_range(0..data.len()));
+            self.trainee.fit(x, y);
+        }
+        let loss_after = self.loss(&data);
+        let path = self.model_path();
+        retry_io(|| self.workspace.save(&mut self.trainee, &path))?;
+        Ok((loss_before, loss_after))
+    }
+
+    /// The function `loss` returns the mean squared error of the trainee on a dataset.
+    fn loss(&mut self, data: &DataSet) -> f64 {
+        let error: f64 = (0..data.len())
+            .map(|k| {
+                let (x, y) = data.get(k);
+                (self.trainee.calc(x)[0] - y[0]).powi(2)
+            })
+            .sum();
+        error / data.len().max(1) as f64
     }
     
     /// The function `calculate_data` takes a `GameTree` as input, calculates predicted rewards based on
@@ -236,17 +549,18 @@ impl Trainer {
     /// to contain information about rewards and positions related to a game. The function retrieves
     /// rewards and positions from the `tree` object, calculates predicted rewards based on a discount
     /// factor and the position index
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn calculate_data(&mut self, tree: GameTree) -> (Vec<Vec<f64>>, Vec<f64>) {
         let rewards = tree.get_rewards();
         let positions = tree.get_positions();
         let mut predicted_rewards = Vec::new();
-        let discount_factor = 0.99; // Adjusted discount factor
+        let discount_factor = self.config.discount_factor;
     
         for i in 0..rewards.len() {
             let mut reward_to_be_pushed: f64 = 0.0;
-            for j in i..rewards.len() {
+            for (j, reward) in rewards.iter().enumerate().skip(i) {
                 let discount = self.power(discount_factor, j - i); // Calculate discount for each time step
-                reward_to_be_pushed += rewards[j] as f64 * discount;
+                reward_to_be_pushed += *reward as f64 * discount;
             }
             predicted_rewards.push(reward_to_be_pushed);
         }
@@ -271,6 +585,7 @@ impl Trainer {
     /// 
     /// The `create_dataset` function returns a `DataSet` object after populating it with the provided
     /// positions and predicted rewards.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn create_dataset(&self, positions: Vec<Vec<f64>>, predicted_rewards: Vec<f64>) -> DataSet {
         let mut data: DataSet = DataSet::new();
         for i in 0..positions.len() {
@@ -280,130 +595,115 @@ impl Trainer {
     }
     
 
-    /// This Rust function `play_game_with_type_2` plays a game with a specific type, updating a game
-    /// tree based on the moves made.
+    /// This Rust function `play_game_with_type_2` plays a game where the opponent moves first,
+    /// updating a game tree based on the moves made.
     /// 
     /// Arguments:
     /// 
-    /// * `trainer`: The `trainer` parameter in the `play_game_with_type_2` function is a reference to a
-    /// string that represents the trainer playing the game. It is used to initialize the game state and
-    /// control the game flow during the gameplay.
+    /// * `opponent`: The `opponent` parameter in the `play_game_with_type_2` function is the opponent
+    /// playing the game, moving first.
+    /// * `rng`: The `rng` parameter draws the exploratory moves of the game.
     /// 
     /// Returns:
     /// 
-    /// The function `play_game_with_type_2` is returning a `GameTree` object.
-    pub fn play_game_with_type_2(&self, trainer: &str) -> GameTree {
-        let (mut tree, mut board, mut board2, mut mm, mut nm, mut turn) = self.game_init_controlled_2(trainer);
-        loop {
-            let mut _move= self.get_move(turn, &mut mm, &board, &mut nm, &board2);
-
-            self.play_move(&mut board2, _move, &mut board);
-            
-            if board.has_game_ended() || board2.has_game_ended() {
-                self.add_reward_on_ending(turn, &board, &board2, &mut tree);
-                break;
-            }
-
-            self.add_reward(turn, &mut tree, &board);
-            
-            turn = !turn;
-        }
-        tree
+    /// The function `play_game_with_type_2` is returning a `GameTree` object, or the error raised
+    /// while loading the players.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn play_game_with_type_2(&self, opponent: Opponent, rng: &mut ChaCha8Rng) -> Result<GameTree> {
+        let mut trainee = self.exploring(self.trainee_player()?);
+        let mut opponent = self.exploring(self.opponent_player(opponent)?);
+        let game = player::play_match(&mut opponent, &mut trainee, &[], None, rng);
+        let mut board: Board= Board::new();
+        board.change_type();
+        Ok(self.record_game(&game.moves, board, Board::new(), false))
     }
 
-    /// This Rust function `play_game_with_type_1` plays a game with a specific type of logic and
+    /// This Rust function `play_game_with_type_1` plays a game where the trainee moves first and
     /// returns a `GameTree`.
     /// 
     /// Arguments:
     /// 
-    /// * `trainer`: The `trainer` parameter in the `play_game_with_type_1` function is a reference to a
-    /// string that represents the trainer in the game. It is used to initialize the game state and
-    /// control the game flow based on the actions of the trainer.
+    /// * `opponent`: The `opponent` parameter in the `play_game_with_type_1` function is the opponent
+    /// in the game, moving second.
+    /// * `rng`: The `rng` parameter draws the exploratory moves of the game.
     /// 
     /// Returns:
     /// 
-    /// The function `play_game_with_type_1` is returning a `GameTree` object.
-    pub fn play_game_with_type_1(&self, trainer: &str) -> GameTree {
-        let (mut tree, mut board, mut board2, mut mm, mut nm, mut turn) = self.game_init_controlled(trainer);
-        loop {
-            let mut _move= self.get_move(turn, &mut mm, &board, &mut nm, &board2);
+    /// The function `play_game_with_type_1` is returning a `GameTree` object, or the error raised
+    /// while loading the players.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn play_game_with_type_1(&self, opponent: Opponent, rng: &mut ChaCha8Rng) -> Result<GameTree> {
+        let mut trainee = self.exploring(self.trainee_player()?);
+        let mut opponent = self.exploring(self.opponent_player(opponent)?);
+        let game = player::play_match(&mut trainee, &mut opponent, &[], None, rng);
+        let mut board2: Board = Board::new();
+        board2.change_type();
+        Ok(self.record_game(&game.moves, Board::new(), board2, true))
+    }
+
+    /// The function `record_game` replays a game on the boards of the two players and builds the
+    /// game tree the trainee learns from.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `moves`: The `moves` parameter lists the moves of the game, first player first.
+    /// * `board`: The `board` parameter is the empty board of the first player.
+    /// * `board2`: The `board2` parameter is the empty board of the second player.
+    /// * `trainee_first`: The `trainee_first` parameter tells whether the trainee moved first.
+    fn record_game(&self, moves: &[i16], mut board: Board, mut board2: Board, trainee_first: bool) -> GameTree {
+        let mut tree = GameTree::new(Vec::new(), Vec::new());
+        let mut turn = true;
+        for _move in moves {
+            self.play_move(&mut board2, *_move, &mut board);
 
-            self.play_move(&mut board2, _move, &mut board);
-            
             if board.has_game_ended() || board2.has_game_ended() {
                 self.add_reward_on_ending(turn, &board, &board2, &mut tree);
+                // `turn` is true on the moves of the first player.
+                tree.set_result(self.game_result(turn == trainee_first, &board, &board2));
+                tree.set_length((board.my_board | board.opp_board).count_ones());
+                tree.set_final_position(board.to_notation());
                 break;
             }
 
             self.add_reward(turn, &mut tree, &board);
-            
+
             turn = !turn;
         }
         tree
     }
-    
-    /// The function `game_init_controlled` initializes a game with controlled settings, including
-    /// creating game boards and minimax algorithms.
-    /// 
-    /// Arguments:
-    /// 
-    /// * `trainer`: The `trainer` parameter in the `game_init_controlled` function is a reference to a
-    /// string that represents the type of training mode for the Minimax algorithm.
-    pub fn game_init_controlled(&self, trainer: &str) -> (GameTree, Board, Board, Minimax, Minimax, bool) {
-        let mut board2: Board = Board::new();
-        board2.change_type();
-        let mut nm = Minimax::new();
-        nm.set_training_mode_with_type(trainer);
-        let turn= true;
-        (GameTree::new(Vec::new(), Vec::new()), Board::new(), board2, Minimax::new(), nm, turn)
+
+    /// The function `trainee_player` loads the trainee, as saved after the last game, to play a game.
+    fn trainee_player(&self) -> Result<Box<dyn Player>> {
+        Ok(Box::new(self.search(&self.model_path())?))
     }
 
-    /// The function `game_init_controlled_2` initializes a game with controlled settings in Rust,
-    /// including setting up the game board, minimax algorithms, and training mode.
-    /// 
-    /// Arguments:
-    /// 
-    /// * `trainer`: The `trainer` parameter in the `game_init_controlled_2` function is a reference to
-    /// a string that specifies the type of training mode for the Minimax algorithm.
-    pub fn game_init_controlled_2(&self, trainer: &str) -> (GameTree, Board, Board, Minimax, Minimax, bool) {
-        let mut board: Board= Board::new();
-        board.change_type();
-        let mut mm= Minimax::new();
-        mm.set_training_mode_with_type(trainer);
-        let turn= true;
-        (GameTree::new(Vec::new(), Vec::new()), board, Board::new(), mm, Minimax::new(), turn)
+    /// The function `search` loads a network as a search, which probes the endgame tablebase of the
+    /// workspace, if there is one, at its leaves.
+    fn search(&self, path: &Path) -> Result<Minimax> {
+        let mut mm = Minimax::new(retry_io(|| self.workspace.load(path))?);
+        mm.set_tablebase(self.tablebase.clone());
+        Ok(mm)
     }
 
-    /// This Rust function `get_move` returns the best move using minimax algorithm based on the turn
-    /// and board state provided.
-    /// 
-    /// Arguments:
-    /// 
-    /// * `turn`: The `turn` parameter is a boolean value that indicates whose turn it is in the game.
-    /// `true` typically represents the player's turn, while `false` represents the opponent's turn.
-    /// * `mm`: Minimax algorithm instance for player 1.
-    /// * `board`: The `board` parameter in the `get_move` function represents the game board state for
-    /// the current player. It is used as input to the minimax algorithm to determine the best move to
-    /// make based on the current game state.
-    /// * `nm`: `nm` appears to be an instance of the `Minimax` struct that is passed by mutable
-    /// reference. It is used to perform the minimax algorithm on `board2`, which is an instance of the
-    /// `Board` struct.
-    /// * `board2`: The `board2` parameter in the `get_move` function represents the second game board
-    /// that is used for the minimax algorithm. It is a reference to a `Board` object which contains the
-    /// state of the game board at a particular point in the game. This board is used by the `
-    /// 
-    /// Returns:
-    /// 
-    /// The function `get_move` returns an `i16` value, which represents the move calculated by the
-    /// Minimax algorithm based on the current game state and player's turn.
-    pub fn get_move(&self, turn: bool, mm: &mut Minimax, board: &Board, nm: &mut Minimax, board2: &Board) -> i16 {
-        let mut _move: i16 = 0;
-        if turn {
-            _move= mm.minimax(board.clone());
-        }else {
-            _move= nm.minimax(board2.clone());
-        }
-        _move
+    /// The function `opponent_player` builds the player of an opponent, loading its network if it has
+    /// one.
+    fn opponent_player(&self, opponent: Opponent) -> Result<Box<dyn Player>> {
+        Ok(match opponent {
+            Opponent::Snapshot(id) => Box::new(self.search(&self.workspace.opponent_path(id))?),
+            Opponent::Heuristic => Box::new(Heuristic::new(self.config.heuristic_opponent.unwrap_or(1.0))),
+            Opponent::Random => Box::new(RandomPlayer),
+            Opponent::NoisyExpert => Box::new(self.config.noisy_expert.noisy(self.config.noisy_expert_opponent.unwrap_or(DEFAULT_ACCURACY))),
+            Opponent::Search => Box::new(Search::new(
+                self.search(&self.model_path())?,
+                SearchLimits { depth: Some(self.config.search_depth), ..SearchLimits::default() },
+            )),
+        })
+    }
+
+    /// The function `exploring` makes a player of a training game play a random move instead of its
+    /// own with the `exploration` probability of the configuration.
+    fn exploring(&self, player: Box<dyn Player>) -> Exploring<Box<dyn Player>> {
+        Exploring { player, exploration: self.config.exploration }
     }
 
     /// The function `add_reward` adds a reward of 0 to a game tree if the turn is true.
@@ -417,6 +717,7 @@ impl Trainer {
     /// * `board`: The `board` parameter in the `add_reward` function is of type `Board`. It is used to
     /// access the game board state in the form of an array through the `get_board_in_array` method.
     /// This array representation of the board is then added to the `GameTree` for further
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn add_reward(&self, turn: bool, tree: &mut GameTree, board: &Board) {
         if turn {
             tree.add_position(board.get_board_in_array());
@@ -439,6 +740,7 @@ impl Trainer {
     /// * `tree`: The `tree` parameter is a mutable reference to a `GameTree` struct. This function
     /// `add_reward_on_ending` takes in several parameters including `turn` which is a boolean flag
     /// indicating the current player's turn, `board` and `board2` which are references to `Board
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn add_reward_on_ending(&self, turn: bool, board: &Board, board2: &Board, tree: &mut GameTree) {
         if turn && (board.is_a_win() || board2.is_a_win()) {
             tree.add_position(board.get_board_in_array());
@@ -452,6 +754,27 @@ impl Trainer {
         }
     }
 
+    /// The function `game_result` gives the outcome of a finished game for the trainee.
+    /// 
+    /// Arguments:
+    /// 
+    /// * `trainee_moved`: The `trainee_moved` parameter tells whether the trainee made the last move.
+    /// * `board`: The `board` parameter is the final position seen by the first player.
+    /// * `board2`: The `board2` parameter is the final position seen by the second player.
+    /// 
+    /// Returns:
+    /// 
+    /// `1` if the trainee won, `-1` if it lost and `0` for a draw.
+    pub fn game_result(&self, trainee_moved: bool, board: &Board, board2: &Board) -> i8 {
+        if !(board.is_a_win() || board2.is_a_win()) {
+            0
+        } else if trainee_moved {
+            1
+        } else {
+            -1
+        }
+    }
+
     /// The function `play_move` takes a move as input and plays it on two different game boards.
     /// 
     /// Arguments:
@@ -463,6 +786,7 @@ impl Trainer {
     /// * `board`: The `board` parameter in the `play_move` function is a mutable reference to a `Board`
     /// object. This parameter is used to call the `play_move` method on the `board` object to play a
     /// move.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn play_move(&self, board2: &mut Board, _move: i16, board: &mut Board) {
         board2.play_move(_move);
         board.play_move(_move);
@@ -480,6 +804,7 @@ impl Trainer {
     /// 
     /// the result of raising the base `x` to the power of `n`, calculated using an optimized algorithm
     /// for exponentiation.
+    #[allow(clippy::doc_lazy_continuation)]
     fn power(&self, x: f64, n: usize) -> f64 {
         if n == 0 {
             return 1.0;
@@ -500,4 +825,31 @@ impl Trainer {
         result
     }
     
-}
\ No newline at end of file
+}
+
+/// The function `retry_io` runs a model read or write, retrying it with an increasing delay so that a
+/// transient file system error does not abort a long training run. Other errors, such as a model that
+/// cannot be decoded, are returned at once.
+/// 
+/// Arguments:
+/// 
+/// * `op`: The `op` parameter is the operation to run, called up to `IO_ATTEMPTS` times.
+/// 
+/// Returns:
+/// 
+/// The result of the first successful attempt, or the error of the last one.
+fn retry_io<T>(mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = RETRY_DELAY;
+    for _attempt in 1..IO_ATTEMPTS {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if !e.is_transient() => return Err(e),
+            Err(e) => {
+                warn!("{}; retrying in {:?}", e, delay);
+                thread::sleep(delay);
+                delay *= 2;
+            }
+        }
+    }
+    op()
+}
@@ -0,0 +1,388 @@
+use std::{fs, path::{Path, PathBuf}, sync::Arc};
+
+use neuroflow::FeedForward;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{error::{Error, Result}, minimax::Minimax, pool::OpponentPool, ratings::Ratings, state::TrainerState, tablebase::Tablebase};
+
+/// The trained model shipped with the sources, which every new workspace starts from.
+const BUNDLED_TRAINEE: &[u8] = include_bytes!("Trainee/acordion.flow");
+
+/// The struct `Workspace` represents a run directory that holds every file produced by an
+/// experiment, so that several experiments can run side by side without touching the source tree.
+///
+/// Properties:
+///
+/// * `root`: The `root` property is the directory under which the trainee model and the opponent pool
+/// of this run are kept.
+#[allow(clippy::doc_lazy_continuation)]
+#[derive(Clone)]
+pub(crate) struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    const TRAINEE_DIR: &'static str = "trainee";
+    const POOL_DIR: &'static str = "pool";
+    const TRAINEE_FILE: &'static str = "acordion.flow";
+    const CONFIG_FILE: &'static str = "trainer.toml";
+    const STATE_FILE: &'static str = "trainer_state.toml";
+    const CHECKPOINT_PREFIX: &'static str = "checkpoint";
+    const CANDIDATE_FILE: &'static str = "candidate.flow";
+    const POOL_FILE: &'static str = "pool.toml";
+    const OPPONENT_PREFIX: &'static str = "acordion-opponent-";
+    const RATINGS_FILE: &'static str = "ratings.toml";
+    const METRICS_FILE: &'static str = "metrics.jsonl";
+    const BOOK_FILE: &'static str = "book.txt";
+    const TABLEBASE_FILE: &'static str = "tablebase.bin";
+
+    /// The function `open` opens the workspace rooted at `root`, creating its directories if they do
+    /// not exist yet and starting its trainee from the bundled model if it has none.
+    ///
+    /// Arguments:
+    ///
+    /// * `root`: The `root` parameter is the path of the run directory. It may be relative to the
+    /// current working directory.
+    ///
+    /// Returns:
+    ///
+    /// The opened `Workspace`, or the error raised while creating its directories or its trainee.
+    #[allow(clippy::doc_lazy_continuation)]
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let workspace = Workspace {
+            root: root.as_ref().to_path_buf(),
+        };
+        for dir in [workspace.trainee_dir(), workspace.pool_dir()] {
+            fs::create_dir_all(&dir).map_err(|e| Error::io(&dir, e))?;
+        }
+        let trainee = workspace.trainee_path();
+        if !trainee.is_file() {
+            let partial = Self::partial_path(&trainee);
+            fs::write(&partial, BUNDLED_TRAINEE).map_err(|e| Error::io(&trainee, e))?;
+            fs::rename(&partial, &trainee).map_err(|e| Error::io(&trainee, e))?;
+        }
+        Ok(workspace)
+    }
+
+    /// The function `root` returns the directory the workspace lives in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The function `trainee_dir` returns the directory holding the model being trained.
+    pub fn trainee_dir(&self) -> PathBuf {
+        self.root.join(Self::TRAINEE_DIR)
+    }
+
+    /// The function `pool_dir` returns the directory holding the snapshots of the opponent pool.
+    pub fn pool_dir(&self) -> PathBuf {
+        self.root.join(Self::POOL_DIR)
+    }
+
+    /// The function `trainee_path` returns the path of the model being trained.
+    pub fn trainee_path(&self) -> PathBuf {
+        self.trainee_dir().join(Self::TRAINEE_FILE)
+    }
+
+    /// The function `config_path` returns the path of the trainer configuration of this run.
+    pub fn config_path(&self) -> PathBuf {
+        self.root.join(Self::CONFIG_FILE)
+    }
+
+    /// The function `state_path` returns the path of the state of an interrupted training run.
+    pub fn state_path(&self) -> PathBuf {
+        self.root.join(Self::STATE_FILE)
+    }
+
+    /// The function `checkpoint_path` returns the path of the trainee as it was when the training state
+    /// of the given iteration was saved.
+    ///
+    /// Arguments:
+    ///
+    /// * `iteration`: The `iteration` parameter is the `iteration` of the training state.
+    pub fn checkpoint_path(&self, iteration: i16) -> PathBuf {
+        self.trainee_dir().join(format!("{}-{}.flow", Self::CHECKPOINT_PREFIX, iteration))
+    }
+
+    /// The function `candidate_path` returns the path of the model being trained when training is
+    /// gated; the trainee is then the champion, only replaced by a candidate that beats it.
+    pub fn candidate_path(&self) -> PathBuf {
+        self.trainee_dir().join(Self::CANDIDATE_FILE)
+    }
+
+    /// The function `book_path` returns the path the opening book of the workspace is written to by
+    /// default.
+    pub fn book_path(&self) -> PathBuf {
+        self.root.join(Self::BOOK_FILE)
+    }
+
+    /// The function `tablebase_path` returns the path the endgame tablebase of the workspace is written
+    /// to by default.
+    pub fn tablebase_path(&self) -> PathBuf {
+        self.root.join(Self::TABLEBASE_FILE)
+    }
+
+    /// The function `pool_path` returns the path of the metadata of the opponent pool.
+    pub fn pool_path(&self) -> PathBuf {
+        self.root.join(Self::POOL_FILE)
+    }
+
+    /// The function `opponent_path` returns the path of the pool snapshot with the given id.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The `id` parameter is the id of the snapshot in the opponent pool.
+    pub fn opponent_path(&self, id: u32) -> PathBuf {
+        self.pool_dir().join(format!("{}{}.flow", Self::OPPONENT_PREFIX, id))
+    }
+
+    /// The function `remove_evicted_opponents` deletes the files of the snapshots that are no longer
+    /// in the pool.
+    ///
+    /// Arguments:
+    ///
+    /// * `pool`: The `pool` parameter is the pool whose snapshots are kept.
+    fn remove_evicted_opponents(&self, pool: &OpponentPool) -> Result<()> {
+        let dir = self.pool_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| Error::io(&dir, e))? {
+            let path = entry.map_err(|e| Error::io(&dir, e))?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let id = name.strip_prefix(Self::OPPONENT_PREFIX).and_then(|name| name.strip_suffix(".flow")).and_then(|id| id.parse::<u32>().ok());
+            if id.is_some_and(|id| !pool.snapshots().iter().any(|snapshot| snapshot.id == id)) {
+                fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The function `load` reads a network from a file of the workspace.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file the network is read from.
+    pub fn load(&self, path: &Path) -> Result<FeedForward> {
+        neuroflow::io::load(&path.to_string_lossy()).map_err(|e| Error::model(path, e))
+    }
+
+    /// The function `save` writes a network to a file of the workspace. The network is written next to
+    /// the file first and then moved over it, so an interrupted save never leaves a truncated model.
+    ///
+    /// Arguments:
+    ///
+    /// * `nn`: The `nn` parameter is the network to be written.
+    /// * `path`: The `path` parameter is the file the network is written to.
+    pub fn save(&self, nn: &mut FeedForward, path: &Path) -> Result<()> {
+        let partial = Self::partial_path(path);
+        neuroflow::io::save(nn, &partial.to_string_lossy()).map_err(|e| Error::model(path, e))?;
+        fs::rename(&partial, path).map_err(|e| Error::io(path, e))
+    }
+
+    /// The function `ratings_path` returns the path of the Elo ratings of the players of this run.
+    pub fn ratings_path(&self) -> PathBuf {
+        self.root.join(Self::RATINGS_FILE)
+    }
+
+    /// The function `metrics_path` returns the path of the JSON Lines log of the training metrics.
+    pub fn metrics_path(&self) -> PathBuf {
+        self.root.join(Self::METRICS_FILE)
+    }
+
+    /// The function `load_ratings` reads the Elo ratings of the workspace.
+    ///
+    /// Returns:
+    ///
+    /// The saved `Ratings`, empty ones if no game has been rated yet, or the error raised while
+    /// reading them.
+    pub fn load_ratings(&self) -> Result<Ratings> {
+        let path = self.ratings_path();
+        if !path.is_file() {
+            return Ok(Ratings::default());
+        }
+        self.read_toml(&path, |path, message| Error::Ratings { path, message })
+    }
+
+    /// The function `save_ratings` writes the Elo ratings of the workspace.
+    ///
+    /// Arguments:
+    ///
+    /// * `ratings`: The `ratings` parameter is the ratings to be written.
+    pub fn save_ratings(&self, ratings: &Ratings) -> Result<()> {
+        self.write_toml(&self.ratings_path(), ratings, |path, message| Error::Ratings { path, message })
+    }
+
+    /// The function `save_state` records the state of the training run together with a copy of the
+    /// trainee it belongs to. The copy is named after the iteration of the state and written first, and
+    /// the copies of earlier states are only removed once the state has replaced the previous one, so
+    /// an interrupted save always leaves a state next to the trainee it was taken with. The opponent
+    /// pool is part of the state; once the state is written, it is copied to `pool.toml` for reading
+    /// and the files of the snapshots it evicted are removed.
+    ///
+    /// Arguments:
+    ///
+    /// * `state`: The `state` parameter is the state to be written.
+    /// * `nn`: The `nn` parameter is the trainee at the time the state was taken.
+    pub fn save_state(&self, state: &TrainerState, nn: &mut FeedForward) -> Result<()> {
+        let checkpoint = self.checkpoint_path(state.iteration);
+        self.save(nn, &checkpoint)?;
+        self.write_toml(&self.state_path(), state, |path, message| Error::State { path, message })?;
+        self.write_toml(&self.pool_path(), &state.pool, |path, message| Error::Pool { path, message })?;
+        self.remove_checkpoints(Some(&checkpoint))?;
+        self.remove_evicted_opponents(&state.pool)
+    }
+
+    /// The function `load_tablebase` reads the endgame tablebase of the workspace, which the searches
+    /// of the trainee and of its opponents probe at their leaves.
+    ///
+    /// Returns:
+    ///
+    /// The tablebase, `None` if none was written with the `tablebase` subcommand, or the error raised
+    /// while reading it.
+    pub fn load_tablebase(&self) -> Result<Option<Arc<Tablebase>>> {
+        let path = self.tablebase_path();
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Tablebase::load_for_game(&path).map(|tablebase| Some(Arc::new(tablebase)))
+    }
+
+    /// The function `load_state` reads the state of an interrupted training run.
+    ///
+    /// Returns:
+    ///
+    /// The saved `TrainerState`, or an error if there is none or it cannot be parsed.
+    pub fn load_state(&self) -> Result<TrainerState> {
+        self.read_toml(&self.state_path(), |path, message| Error::State { path, message })
+    }
+
+    /// The function `load_checkpoint` reads the trainee saved with a training state.
+    ///
+    /// Arguments:
+    ///
+    /// * `state`: The `state` parameter is the state read with `load_state`.
+    pub fn load_checkpoint(&self, state: &TrainerState) -> Result<FeedForward> {
+        self.load(&self.checkpoint_path(state.iteration))
+    }
+
+    /// The function `clear_state` removes the training state and its checkpoint once a run completes.
+    pub fn clear_state(&self) -> Result<()> {
+        let path = self.state_path();
+        if path.is_file() {
+            fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+        }
+        self.remove_checkpoints(None)
+    }
+
+    /// The function `remove_checkpoints` deletes the checkpoints of the trainee directory.
+    ///
+    /// Arguments:
+    ///
+    /// * `keep`: The `keep` parameter is a checkpoint to leave in place, if any.
+    fn remove_checkpoints(&self, keep: Option<&Path>) -> Result<()> {
+        let dir = self.trainee_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| Error::io(&dir, e))? {
+            let path = entry.map_err(|e| Error::io(&dir, e))?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with(Self::CHECKPOINT_PREFIX) && name.ends_with(".flow") && Some(path.as_path()) != keep {
+                fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The function `partial_path` returns the temporary file a file is written to before being moved
+    /// into place.
+    fn partial_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".partial");
+        path.with_file_name(name)
+    }
+
+    /// The function `load_trainee` reads the model being trained.
+    pub fn load_trainee(&self) -> Result<FeedForward> {
+        self.load(&self.trainee_path())
+    }
+
+    /// The function `trainee_search` loads the trainee as a search, which probes the endgame tablebase
+    /// of the workspace, if there is one, at its leaves.
+    pub fn trainee_search(&self) -> Result<Minimax> {
+        let mut mm = Minimax::new(self.load_trainee()?);
+        mm.set_tablebase(self.load_tablebase()?);
+        Ok(mm)
+    }
+
+    /// The function `save_trainee` overwrites the model being trained.
+    ///
+    /// Arguments:
+    ///
+    /// * `nn`: The `nn` parameter is the network that becomes the new trainee.
+    pub fn save_trainee(&self, nn: &mut FeedForward) -> Result<()> {
+        self.save(nn, &self.trainee_path())
+    }
+
+    /// The function `export_trainee` copies the model being trained to a file outside the workspace.
+    ///
+    /// Arguments:
+    ///
+    /// * `output`: The `output` parameter is the file the trainee is written to.
+    /// * `json`: The `json` parameter selects JSON output instead of the binary `.flow` format.
+    pub fn export_trainee(&self, output: &Path, json: bool) -> Result<()> {
+        let mut nn = self.load_trainee()?;
+        if json {
+            let text = neuroflow::io::to_json(&nn).map_err(|e| Error::model(output, e))?;
+            fs::write(output, text).map_err(|e| Error::io(output, e))
+        } else {
+            self.save(&mut nn, output)
+        }
+    }
+
+    /// The `clear_pool` function deletes every snapshot of the opponent pool and its metadata.
+    ///
+    /// Returns:
+    ///
+    /// `Ok(())` if all the files could be removed, otherwise the first I/O error encountered.
+    pub fn clear_pool(&self) -> Result<()> {
+        let dir = self.pool_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| Error::io(&dir, e))? {
+            let path = entry.map_err(|e| Error::io(&dir, e))?.path();
+            if path.is_file() {
+                fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+            }
+        }
+        let path = self.pool_path();
+        if path.is_file() {
+            fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+        }
+        Ok(())
+    }
+
+    /// The function `write_toml` serializes a value to a TOML file of the workspace, replacing the
+    /// file only once it has been completely written.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be written.
+    /// * `value`: The `value` parameter is the value to be serialized.
+    /// * `invalid`: The `invalid` parameter builds the error reported when the value cannot be
+    /// serialized, from the path and the message of the failure.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn write_toml<T: Serialize>(&self, path: &Path, value: &T, invalid: fn(PathBuf, String) -> Error) -> Result<()> {
+        let text = toml::to_string(value).map_err(|e| invalid(path.to_path_buf(), e.to_string()))?;
+        let partial = Self::partial_path(path);
+        fs::write(&partial, text).map_err(|e| Error::io(path, e))?;
+        fs::rename(&partial, path).map_err(|e| Error::io(path, e))
+    }
+
+    /// The function `read_toml` deserializes a value from a TOML file of the workspace.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The `path` parameter is the file to be read.
+    /// * `invalid`: The `invalid` parameter builds the error reported when the file cannot be parsed,
+    /// from the path and the message of the failure.
+    #[allow(clippy::doc_lazy_continuation)]
+    fn read_toml<T: DeserializeOwned>(&self, path: &Path, invalid: fn(PathBuf, String) -> Error) -> Result<T> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        toml::from_str(&text).map_err(|e| invalid(path.to_path_buf(), e.to_string()))
+    }
+}